@@ -9,6 +9,7 @@ use bevy::{
         schedule::{IntoSystemConfigs, IntoSystemSetConfigs},
         system::Query,
     },
+    math::DVec3,
     render::camera::Camera,
     transform::components::Transform,
 };
@@ -36,8 +37,9 @@ pub fn floating_origin_transform(
     mut bodies: Query<(&mut Transform, &FloatingOriginPosition)>,
 ) {
     //info!("floating_origin_transform");
-    let camera_position = camera.get_single().expect("");
+    // Without a camera, e.g. when running headless, the origin stays fixed
+    let camera_position = camera.get_single().map_or(DVec3::ZERO, |c| c.0);
     for (mut body_transform, body_position) in bodies.iter_mut() {
-        body_transform.translation = (body_position.0 - camera_position.0).as_vec3();
+        body_transform.translation = (body_position.0 - camera_position).as_vec3();
     }
 }
@@ -0,0 +1,127 @@
+use std::time::Instant;
+
+use bevy::math::DVec3;
+
+use crate::physics::{
+    nbody::GravityModel,
+    resources::{GravityParallelism, GravitySolver},
+};
+
+/// Opening angles of the Barnes-Hut solver compared against the direct sum
+const OPENING_ANGLES: [f64; 4] = [0.3, 0.5, 0.7, 1.0];
+/// MassG of the central star in m^3/s^2
+const STAR_MASS: f64 = 1.327e20;
+const ASTRONOMICAL_UNIT: f64 = 1.496e11;
+
+/// Small deterministic generator, so every benchmark run uses the same belt
+struct Lcg(u64);
+impl Lcg {
+    /// Uniform number in [0, 1)
+    fn next(&mut self) -> f64 {
+        self.0 = self
+            .0
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        (self.0 >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+/// Star surrounded by an asteroid belt between 2 and 4 AU
+pub fn asteroid_belt(bodies: usize) -> (Vec<DVec3>, Vec<f64>) {
+    let mut random = Lcg(42);
+    let mut positions = vec![DVec3::ZERO];
+    let mut masses = vec![STAR_MASS];
+    for _ in 1..bodies {
+        let radius = (2.0 + 2.0 * random.next()) * ASTRONOMICAL_UNIT;
+        let angle = random.next() * std::f64::consts::TAU;
+        let height = (random.next() - 0.5) * 0.1 * ASTRONOMICAL_UNIT;
+        positions.push(DVec3::new(
+            radius * angle.cos(),
+            radius * angle.sin(),
+            height,
+        ));
+        masses.push(10f64.powf(5.0 + 4.0 * random.next()));
+    }
+    (positions, masses)
+}
+
+/// Runs the solver until at least a second passed and returns the mean duration in ms
+fn time_solver(
+    model: &GravityModel,
+    positions: &[DVec3],
+    masses: &[f64],
+    out: &mut [DVec3],
+) -> f64 {
+    let start = Instant::now();
+    let mut runs = 0;
+    while runs == 0 || start.elapsed().as_secs_f64() < 1.0 {
+        model.accelerations(positions, masses, out);
+        runs += 1;
+    }
+    start.elapsed().as_secs_f64() * 1000.0 / runs as f64
+}
+
+///
+/// Compares the serial direct sum with its parallel variants and the Barnes-Hut solver
+/// for a generated asteroid belt. Prints the time per evaluation, the relative error
+/// against the serial direct sum and whether the result is bit-identical to it.
+///
+pub fn run_gravity_benchmark(bodies: usize) {
+    let (positions, masses) = asteroid_belt(bodies.max(2));
+    let mut exact = vec![DVec3::ZERO; positions.len()];
+    let mut compared = vec![DVec3::ZERO; positions.len()];
+
+    let serial = GravityModel {
+        parallelism: GravityParallelism::Serial,
+        ..Default::default()
+    };
+    let serial_ms = time_solver(&serial, &positions, &masses, &mut exact);
+    println!("{} bodies", positions.len());
+    println!("solver       theta   parallel        time [ms]   max error   rms error   identical");
+    println!(
+        "{:<10} {:>6}   {:<14} {:>10.3}   {:>9}   {:>9}   {:>9}",
+        "direct", "-", "serial", serial_ms, "-", "-", "yes"
+    );
+
+    let mut models = vec![
+        GravityModel {
+            parallelism: GravityParallelism::Deterministic,
+            ..Default::default()
+        },
+        GravityModel {
+            parallelism: GravityParallelism::Fast,
+            ..Default::default()
+        },
+    ];
+    models.extend(OPENING_ANGLES.iter().map(|opening_angle| GravityModel {
+        solver: GravitySolver::BarnesHut,
+        opening_angle: *opening_angle,
+        ..Default::default()
+    }));
+
+    for model in models {
+        let ms = time_solver(&model, &positions, &masses, &mut compared);
+
+        let errors: Vec<f64> = exact
+            .iter()
+            .zip(compared.iter())
+            .map(|(e, a)| (*a - *e).length() / e.length())
+            .collect();
+        let max = errors.iter().cloned().fold(0.0, f64::max);
+        let rms = (errors.iter().map(|e| e * e).sum::<f64>() / errors.len() as f64).sqrt();
+        let (solver, theta) = match model.solver {
+            GravitySolver::DirectSum => ("direct", "-".to_string()),
+            GravitySolver::BarnesHut => ("barnes-hut", format!("{:.2}", model.opening_angle)),
+        };
+        println!(
+            "{:<10} {:>6}   {:<14} {:>10.3}   {:>9.2e}   {:>9.2e}   {:>9}",
+            solver,
+            theta,
+            format!("{:?}", model.parallelism).to_lowercase(),
+            ms,
+            max,
+            rms,
+            if exact == compared { "yes" } else { "no" }
+        );
+    }
+}
@@ -0,0 +1,135 @@
+pub mod benchmark;
+
+use std::fs::write;
+
+use bevy::{
+    app::App,
+    core::Name,
+    ecs::{query::With, world::World},
+    MinimalPlugins,
+};
+use serde::Serialize;
+
+use crate::{
+    floatingorigin::{components::FloatingOriginPosition, FloatingOriginPlugin},
+    objects::{components::Craft, HeadlessObjectsPlugins},
+    physics::{
+        components::{Landed, NBodyEffector, NBodyVelocity, PrimaryBody},
+        epoch::TimeScale,
+        resources::{PhysicsDirection, PhysicsElapsed, PhysicsTimeScale},
+        step_physics, PhysicPlugin,
+    },
+    save::{
+        keyframes::{seek, KeyframePlugin},
+        take_snapshot, write_snapshot, SavePlugin,
+    },
+    utils::{
+        arguments::ParsedArguments,
+        data::{find_data_file, DataDir},
+    },
+};
+
+/// Final state of a single body after the headless propagation
+#[derive(Serialize)]
+struct BodyState {
+    name: String,
+    craft: bool,
+    /// Craft rests on the surface of a planet after an impact
+    landed: bool,
+    primary: Option<String>,
+    position: [f64; 3],
+    velocity: [f64; 3],
+}
+
+/// Propagation result written after the headless run
+#[derive(Serialize)]
+struct PropagationResult {
+    /// Epoch at the end of the propagation in UTC
+    epoch: String,
+    elapsed: f64,
+    steps: u64,
+    bodies: Vec<BodyState>,
+}
+
+///
+/// Propagates the bodies in the data directory for the requested simulated duration without a window.
+/// The physics schedule is stepped directly, so the run is not limited by real time.
+///
+pub fn run_headless(args: &ParsedArguments) {
+    let mut app = App::new();
+    app.add_plugins((
+        MinimalPlugins,
+        HeadlessObjectsPlugins,
+        PhysicPlugin,
+        FloatingOriginPlugin,
+        SavePlugin,
+        KeyframePlugin,
+    ));
+    args.insert_resources(&mut app);
+
+    // Steps are only run forwards here, the time scale of a scenario only applies to the window
+    app.insert_resource(PhysicsTimeScale(1))
+        .insert_resource(PhysicsDirection::Forward);
+
+    // Runs the startup systems to spawn all bodies and restore a snapshot
+    app.finish();
+    app.cleanup();
+    app.update();
+
+    // The duration counts from the start of the simulation, also when resuming a snapshot
+    while app.world.resource::<PhysicsElapsed>().seconds < args.duration {
+        step_physics(&mut app.world);
+    }
+    if let Some(target) = args.seek {
+        seek(&mut app.world, target).expect("Unable to seek");
+    }
+
+    if !args.save.is_empty() {
+        let path = find_data_file(DataDir::Saves, &args.save);
+        write_snapshot(&path, &take_snapshot(&mut app.world)).expect("Unable to write snapshot");
+        println!("Saved snapshot {}", path.display());
+    }
+
+    let result = serde_json::to_string_pretty(&collect_states(&mut app.world)).expect("");
+    if args.output.is_empty() {
+        println!("{}", result);
+    } else {
+        write(&args.output, result).expect("Unable to write output file");
+    }
+}
+
+fn collect_states(world: &mut World) -> PropagationResult {
+    let elapsed = world.resource::<PhysicsElapsed>();
+    let (seconds, steps, now) = (elapsed.seconds, elapsed.steps, elapsed.now());
+
+    let mut names_q = world.query::<&Name>();
+    let mut bodies_q = world.query_filtered::<(
+        &Name,
+        &FloatingOriginPosition,
+        &NBodyVelocity,
+        Option<&Craft>,
+        Option<&PrimaryBody>,
+        Option<&Landed>,
+    ), With<NBodyEffector>>();
+
+    PropagationResult {
+        epoch: now.format(TimeScale::Utc),
+        elapsed: seconds,
+        steps,
+        bodies: bodies_q
+            .iter(world)
+            .map(
+                |(name, position, velocity, craft, primary, landed)| BodyState {
+                    name: name.to_string(),
+                    craft: craft.is_some(),
+                    landed: landed.is_some(),
+                    primary: primary
+                        .and_then(|p| names_q.get(world, p.0).ok())
+                        .map(|n| n.to_string()),
+                    position: position.0.to_array(),
+                    velocity: velocity.0.to_array(),
+                },
+            )
+            .collect(),
+    }
+}
@@ -0,0 +1,566 @@
+use std::{fs::read_to_string, path::PathBuf, str::FromStr};
+
+use bevy::math::DVec3;
+use physical_constants::NEWTONIAN_CONSTANT_OF_GRAVITATION;
+
+use crate::physics::epoch::{Epoch, TimeScale};
+
+use super::{existing_kind, BodyKind, CelestialFrame, ImportedBody, PhysicalProperties};
+
+const ASTRONOMICAL_UNIT: f64 = 1.495978707e11;
+const SEC_PER_DAY: f64 = 86400.0;
+/// Epochs of tables combined in one import may differ by this many seconds
+const EPOCH_TOLERANCE: f64 = 1.0;
+
+/// State vectors of a single target exported by the JPL Horizons system, converted to SI units
+#[derive(Debug, Clone)]
+pub struct HorizonsTable {
+    pub target: String,
+    /// Horizons id of the target, negative for spacecraft
+    pub target_id: Option<i64>,
+    pub center: String,
+    pub frame: CelestialFrame,
+    pub properties: PhysicalProperties,
+    /// Epoch, position and velocity of every row
+    pub rows: Vec<(Epoch, DVec3, DVec3)>,
+}
+impl HorizonsTable {
+    /// Row closest to the given epoch
+    pub fn state_at(&self, epoch: Epoch) -> (Epoch, DVec3, DVec3) {
+        *self
+            .rows
+            .iter()
+            .min_by(|a, b| {
+                (a.0 .0 - epoch.0)
+                    .abs()
+                    .total_cmp(&(b.0 .0 - epoch.0).abs())
+            })
+            .expect("")
+    }
+
+    /// Planets become planet files, spacecraft become craft files unless a file of the other kind exists
+    pub fn kind(&self) -> BodyKind {
+        existing_kind(&self.target).unwrap_or(match self.target_id {
+            Some(id) if id < 0 => BodyKind::Craft,
+            _ => BodyKind::Planet,
+        })
+    }
+}
+impl FromStr for HorizonsTable {
+    type Err = String;
+
+    ///
+    /// Parses a vector table in the text or CSV layout of Horizons.
+    /// Units are read from the header and may be KM-S, KM-D or AU-D,
+    /// tables without a reference frame line use the ecliptic default of Horizons.
+    ///
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (header, rest) = s
+            .split_once("$$SOE")
+            .ok_or("Missing $$SOE, not a Horizons vector table")?;
+        let (data, _) = rest
+            .split_once("$$EOE")
+            .ok_or("Missing $$EOE, not a Horizons vector table")?;
+
+        let (target, target_id) =
+            parse_name(header_line(header, "target body name").ok_or("Missing target body name")?);
+        let (center, _) =
+            parse_name(header_line(header, "center body name").ok_or("Missing center body name")?);
+
+        let frame = match header_line(header, "reference frame")
+            .or_else(|| header_line(header, "reference plane"))
+        {
+            Some(frame) if !frame.to_lowercase().contains("ecliptic") => CelestialFrame::Icrf,
+            _ => CelestialFrame::Ecliptic,
+        };
+
+        let units = header_line(header, "output units")
+            .unwrap_or("KM-S")
+            .to_uppercase();
+        let length_scale = if units.starts_with("AU") {
+            ASTRONOMICAL_UNIT
+        } else {
+            1000.0
+        };
+        let time_scale = if units.ends_with('D') {
+            SEC_PER_DAY
+        } else {
+            1.0
+        };
+
+        let rows = if data.contains(',') {
+            parse_csv_rows(header, data)?
+        } else {
+            parse_text_rows(data)?
+        };
+        if rows.is_empty() {
+            return Err(format!("No state vectors found for {}", target));
+        }
+
+        Ok(HorizonsTable {
+            target,
+            target_id,
+            center,
+            frame,
+            properties: parse_properties(header),
+            rows: rows
+                .into_iter()
+                .map(|(epoch, position, velocity)| {
+                    (
+                        epoch,
+                        position * length_scale,
+                        velocity * length_scale / time_scale,
+                    )
+                })
+                .collect(),
+        })
+    }
+}
+
+/// Value of the first header line starting with the given key, e.g. "Target body name: Earth (399)"
+fn header_line<'a>(header: &'a str, key: &str) -> Option<&'a str> {
+    header
+        .lines()
+        .find(|line| line.trim_start().to_lowercase().starts_with(key))
+        .and_then(|line| line.split_once(':'))
+        .map(|(_, value)| value.trim())
+}
+
+/// Splits "Voyager 1 (spacecraft) (-31)   {source: ...}" into name and id
+fn parse_name(value: &str) -> (String, Option<i64>) {
+    let value = value.split('{').next().unwrap_or_default();
+    let mut name = String::new();
+    let mut id = None;
+    let mut rest = value;
+    while let Some(open) = rest.find('(') {
+        name.push_str(&rest[..open]);
+        let close = rest[open..].find(')').map_or(rest.len(), |c| open + c);
+        if let Ok(number) = rest[open + 1..close].trim().parse::<i64>() {
+            id = Some(number);
+        }
+        rest = &rest[(close + 1).min(rest.len())..];
+    }
+    name.push_str(rest);
+    (name.split_whitespace().collect::<Vec<_>>().join(" "), id)
+}
+
+/// Longest prefix of the text which is a number, e.g. 6371.01 of "6371.01+-0.02"
+fn leading_number(text: &str) -> Option<f64> {
+    let text = text.trim_start();
+    let candidate: String = text
+        .chars()
+        .take_while(|c| c.is_ascii_digit() || ".eE+-".contains(*c))
+        .collect();
+    (1..=candidate.len())
+        .rev()
+        .find_map(|len| candidate[..len].parse::<f64>().ok())
+}
+
+/// Number after the equal sign following the first of the given keys in the header
+fn header_value(header: &str, keys: &[&str]) -> Option<f64> {
+    let lower = header.to_lowercase();
+    keys.iter().find_map(|key| {
+        let start = lower.find(key)? + key.len();
+        let equal = lower[start..].find('=')? + start + 1;
+        leading_number(&lower[equal..])
+    })
+}
+
+/// Mass, radius and rotation from the physical data section of major bodies
+fn parse_properties(header: &str) -> PhysicalProperties {
+    let gm = header_value(
+        header,
+        &[
+            "gm, km^3/s^2",
+            "gm, km^3 s^-2",
+            "gm (km^3/s^2)",
+            "gm, km^3/s^-2",
+        ],
+    );
+    // Mass is also given as "Mass x10^24 (kg)= 5.97219"
+    let lower = header.to_lowercase();
+    let scaled_mass = lower.find("mass x10^").and_then(|start| {
+        let exponent = leading_number(&lower[start + 9..])?;
+        let mass = header_value(&lower[start..], &["mass x10^"])?;
+        Some(mass * 10f64.powf(exponent))
+    });
+
+    PhysicalProperties {
+        mass: gm
+            .map(|gm| gm * 1e9 / NEWTONIAN_CONSTANT_OF_GRAVITATION)
+            .or(scaled_mass),
+        radius: header_value(
+            header,
+            &[
+                "vol. mean radius (km)",
+                "vol. mean radius, km",
+                "mean radius (km)",
+                "mean radius, km",
+                "radius (km)",
+            ],
+        )
+        .map(|r| r * 1000.0),
+        axial_tilt: header_value(header, &["obliquity to orbit"]).map(f64::to_radians),
+        angular_velocity: header_value(
+            header,
+            &[
+                "rot. rate, rad/s",
+                "rot. rate (rad/s)",
+                "rot. rate, (rad/s)",
+            ],
+        ),
+    }
+}
+
+/// Rows of the text layout, a Julian date line followed by X =, Y =, ... VZ= values
+fn parse_text_rows(data: &str) -> Result<Vec<(Epoch, DVec3, DVec3)>, String> {
+    let mut rows = Vec::new();
+    let mut current: Option<(Epoch, [Option<f64>; 6])> = None;
+    for line in data.lines().map(str::trim).filter(|l| !l.is_empty()) {
+        let first = line.split_whitespace().next().unwrap_or_default();
+        if let Ok(julian_date) = first.parse::<f64>() {
+            rows.extend(current.take().map(complete_row).transpose()?);
+            let scale = if line.contains(" UT") {
+                TimeScale::Utc
+            } else {
+                TimeScale::Tdb
+            };
+            current = Some((Epoch::from_julian_date(julian_date, scale), [None; 6]));
+            continue;
+        }
+
+        let Some((_, values)) = current.as_mut() else {
+            return Err(format!("Values before the first epoch: {}", line));
+        };
+        // "X =-1.49E+08 Y = 2.37E+06 Z = 1.2E+04", the key precedes each equal sign
+        let parts: Vec<&str> = line.split('=').collect();
+        for pair in parts.windows(2) {
+            let key = pair[0].split_whitespace().last().unwrap_or_default();
+            if let (Some(index), Some(value)) = (component_index(key), leading_number(pair[1])) {
+                values[index] = Some(value);
+            }
+        }
+    }
+    rows.extend(current.take().map(complete_row).transpose()?);
+    Ok(rows)
+}
+
+/// Rows of the CSV layout, the column names are taken from the header line above $$SOE
+fn parse_csv_rows(header: &str, data: &str) -> Result<Vec<(Epoch, DVec3, DVec3)>, String> {
+    let columns: Vec<String> = header
+        .lines()
+        .rev()
+        .find(|line| line.contains("VX"))
+        .ok_or("Missing CSV column names")?
+        .split(',')
+        .map(|c| c.trim().to_uppercase())
+        .collect();
+    let date_column = columns
+        .iter()
+        .position(|c| c.starts_with("JD"))
+        .ok_or("Missing Julian date column")?;
+    let scale = if columns[date_column] == "JDUT" {
+        TimeScale::Utc
+    } else {
+        TimeScale::Tdb
+    };
+
+    let mut rows = Vec::new();
+    for line in data.lines().map(str::trim).filter(|l| !l.is_empty()) {
+        let cells: Vec<&str> = line.split(',').map(str::trim).collect();
+        let julian_date = cells
+            .get(date_column)
+            .and_then(|c| c.parse::<f64>().ok())
+            .ok_or_else(|| format!("Invalid Julian date in {}", line))?;
+        let mut values = [None; 6];
+        for (column, cell) in columns.iter().zip(cells.iter()) {
+            if let Some(index) = component_index(column) {
+                values[index] = cell.parse::<f64>().ok();
+            }
+        }
+        rows.push(complete_row((
+            Epoch::from_julian_date(julian_date, scale),
+            values,
+        ))?);
+    }
+    Ok(rows)
+}
+
+fn component_index(key: &str) -> Option<usize> {
+    ["X", "Y", "Z", "VX", "VY", "VZ"]
+        .iter()
+        .position(|k| k.eq_ignore_ascii_case(key))
+}
+
+fn complete_row(
+    (epoch, values): (Epoch, [Option<f64>; 6]),
+) -> Result<(Epoch, DVec3, DVec3), String> {
+    let missing = || {
+        format!(
+            "Incomplete state vector at {}",
+            epoch.format(TimeScale::Tdb)
+        )
+    };
+    let mut v = [0.0; 6];
+    for (value, read) in v.iter_mut().zip(values) {
+        *value = read.ok_or_else(missing)?;
+    }
+    Ok((
+        epoch,
+        DVec3::new(v[0], v[1], v[2]),
+        DVec3::new(v[3], v[4], v[5]),
+    ))
+}
+
+///
+/// Reads Horizons vector tables and returns the state of every target at the row closest to the epoch.
+/// Without an epoch the first row of the first table is used.
+/// All tables need the same Horizons center body. If a center is given, the states are made relative
+/// to that body, which must be one of the targets. The vectors are rotated into the requested frame.
+///
+pub fn import_horizons(
+    paths: &[PathBuf],
+    center: Option<&str>,
+    frame: CelestialFrame,
+    epoch: Option<Epoch>,
+) -> Result<Vec<ImportedBody>, String> {
+    let mut tables = Vec::new();
+    for path in paths {
+        let text = read_to_string(path)
+            .map_err(|e| format!("Unable to read {}: {}", path.display(), e))?;
+        let table = text
+            .parse::<HorizonsTable>()
+            .map_err(|e| format!("{}: {}", path.display(), e))?;
+        tables.push(table);
+    }
+    let Some(first) = tables.first() else {
+        return Ok(Vec::new());
+    };
+    let epoch = epoch.unwrap_or(first.rows[0].0);
+
+    if let Some(other) = tables.iter().find(|t| t.center != first.center) {
+        return Err(format!(
+            "{} is relative to {} but {} to {}, export all tables with the same center",
+            first.target, first.center, other.target, other.center
+        ));
+    }
+
+    let states: Vec<_> = tables
+        .iter()
+        .map(|table| {
+            let (row_epoch, position, velocity) = table.state_at(epoch);
+            (
+                row_epoch,
+                table.frame.transform(frame, position),
+                table.frame.transform(frame, velocity),
+            )
+        })
+        .collect();
+    let row_epoch = states[0].0;
+    if let Some(table) = tables
+        .iter()
+        .zip(states.iter())
+        .find(|(_, state)| (state.0 .0 - row_epoch.0).abs() > EPOCH_TOLERANCE)
+        .map(|(table, _)| table)
+    {
+        return Err(format!(
+            "{} has no state at {}",
+            table.target,
+            row_epoch.format(TimeScale::Tdb)
+        ));
+    }
+
+    let (center_position, center_velocity) = match center {
+        Some(name) => tables
+            .iter()
+            .zip(states.iter())
+            .find(|(table, _)| table.target.eq_ignore_ascii_case(name))
+            .map(|(_, state)| (state.1, state.2))
+            .ok_or_else(|| format!("Center body {} is not among the imported tables", name))?,
+        None => (DVec3::ZERO, DVec3::ZERO),
+    };
+
+    Ok(tables
+        .iter()
+        .zip(states)
+        .map(|(table, (epoch, position, velocity))| ImportedBody {
+            name: table.target.clone(),
+            kind: table.kind(),
+            epoch,
+            position: position - center_position,
+            velocity: velocity - center_velocity,
+            properties: table.properties,
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Excerpt of a Horizons vector table of the Earth relative to the Sun in the text layout
+    const EARTH_TEXT: &str = r#"
+*******************************************************************************
+ Revised: April 12, 2021                 Earth                              399
+
+ GEOPHYSICAL PROPERTIES (revised May 9, 2022):
+  Vol. Mean Radius (km)    = 6371.01+-0.02   Mass x10^24 (kg)= 5.97219+-0.0006
+  Equ. radius, km          = 6378.137        Mass layers:
+  Polar axis, km           = 6356.752          Atmos         = 4.87e-06 (kg)
+  GM, km^3/s^2             = 398600.435436   Inner core rad  = 1215 km
+  GM 1-sigma, km^3/s^2     =      0.0014     Escape velocity = 11.186 km/s
+  Rot. Rate (rad/s)        = 0.00007292115   Surface area:
+  Mean sidereal day, hr    = 23.9344695944     land          = 1.48 x 10^8 km
+ HELIOCENTRIC ORBIT CHARACTERISTICS:
+  Obliquity to orbit, deg  = 23.4392911  Sidereal orb period  = 1.0000174 y
+*******************************************************************************
+Ephemeris / WWW_USER Sat Mar 16 10:12:05 2024 Pasadena, USA      / Horizons
+*******************************************************************************
+Target body name: Earth (399)                     {source: DE441}
+Center body name: Sun (10)                        {source: DE441}
+Center-site name: BODY CENTER
+*******************************************************************************
+Start time      : A.D. 2024-Jan-01 00:00:00.0000 TDB
+Stop  time      : A.D. 2024-Jan-02 00:00:00.0000 TDB
+Step-size       : 1440 minutes
+*******************************************************************************
+Center geodetic : 0.0, 0.0, 0.0                   {E-lon(deg),Lat(deg),Alt(km)}
+Center radii    : 695700.0, 695700.0, 695700.0 km {Equator, meridian, pole}
+Output units    : KM-S
+Calendar mode   : Mixed Julian/Gregorian
+Output type     : GEOMETRIC cartesian states
+Output format   : 3 (position, velocity, LT, range, range-rate)
+Reference frame : Ecliptic of J2000.0
+*******************************************************************************
+JDTDB
+   X     Y     Z
+   VX    VY    VZ
+   LT    RG    RR
+*******************************************************************************
+$$SOE
+2460310.500000000 = A.D. 2024-Jan-01 00:00:00.0000 TDB 
+ X =-2.524807055010216E+07 Y = 1.449728003229276E+08 Z =-6.229418542231619E+03
+ VX=-2.983158619322519E+01 VY=-5.220044183975937E+00 VZ= 1.286133524029806E-03
+ LT= 4.908601616706467E+02 RG= 1.471550467034474E+08 RR=-1.709029616346449E-02
+2460311.500000000 = A.D. 2024-Jan-02 00:00:00.0000 TDB 
+ X =-2.781938006632519E+07 Y = 1.445081521009935E+08 Z =-6.103727812503278E+03
+ VX=-2.963419071283361E+01 VY=-5.535389428731205E+00 VZ= 1.623125411049932E-03
+ LT= 4.908514049834573E+02 RG= 1.471524215009836E+08 RR=-3.361244006524912E-02
+$$EOE
+*******************************************************************************
+"#;
+
+    /// The same states in the CSV layout
+    const EARTH_CSV: &str = r#"
+*******************************************************************************
+Target body name: Earth (399)                     {source: DE441}
+Center body name: Sun (10)                        {source: DE441}
+*******************************************************************************
+Output units    : KM-S
+Reference frame : Ecliptic of J2000.0
+*******************************************************************************
+            JDTDB,            Calendar Date (TDB),                      X,                      Y,                      Z,                     VX,                     VY,                     VZ,
+**************************************************************************************************************************************************************************************************
+$$SOE
+2460310.500000000, A.D. 2024-Jan-01 00:00:00.0000, -2.524807055010216E+07,  1.449728003229276E+08, -6.229418542231619E+03, -2.983158619322519E+01, -5.220044183975937E+00,  1.286133524029806E-03,
+2460311.500000000, A.D. 2024-Jan-02 00:00:00.0000, -2.781938006632519E+07,  1.445081521009935E+08, -6.103727812503278E+03, -2.963419071283361E+01, -5.535389428731205E+00,  1.623125411049932E-03,
+$$EOE
+**************************************************************************************************************************************************************************************************
+"#;
+
+    #[test]
+    fn text_table() {
+        let table = EARTH_TEXT.parse::<HorizonsTable>().expect("");
+        assert_eq!(table.target, "Earth");
+        assert_eq!(table.target_id, Some(399));
+        assert_eq!(table.center, "Sun");
+        assert_eq!(table.frame, CelestialFrame::Ecliptic);
+
+        assert_eq!(table.rows.len(), 2);
+        let (epoch, position, velocity) = table.rows[0];
+        assert_eq!(epoch, Epoch::from_julian_date(2460310.5, TimeScale::Tdb));
+        assert_eq!(
+            position,
+            DVec3::new(
+                -2.524807055010216E+07,
+                1.449728003229276E+08,
+                -6.229418542231619E+03
+            ) * 1000.0
+        );
+        assert_eq!(
+            velocity,
+            DVec3::new(
+                -2.983158619322519E+01,
+                -5.220044183975937E+00,
+                1.286133524029806E-03
+            ) * 1000.0
+        );
+
+        let properties = table.properties;
+        assert!((properties.mass.expect("") / 5.97219e24 - 1.0).abs() < 1e-4);
+        assert_eq!(properties.radius, Some(6371010.0));
+        assert_eq!(properties.axial_tilt, Some(23.4392911_f64.to_radians()));
+        assert_eq!(properties.angular_velocity, Some(7.292115e-5));
+    }
+
+    #[test]
+    fn csv_table() {
+        let text = EARTH_TEXT.parse::<HorizonsTable>().expect("");
+        let csv = EARTH_CSV.parse::<HorizonsTable>().expect("");
+        assert_eq!(csv.target, "Earth");
+        assert_eq!(csv.center, "Sun");
+        assert_eq!(csv.rows, text.rows);
+    }
+
+    #[test]
+    fn units_and_frame() {
+        let days = EARTH_TEXT
+            .replace("KM-S", "KM-D")
+            .replace("Ecliptic of J2000.0", "ICRF")
+            .parse::<HorizonsTable>()
+            .expect("");
+        let seconds = EARTH_TEXT.parse::<HorizonsTable>().expect("");
+        assert_eq!(days.frame, CelestialFrame::Icrf);
+        assert_eq!(days.rows[0].1, seconds.rows[0].1);
+        assert!((days.rows[0].2 * SEC_PER_DAY - seconds.rows[0].2).length() < 1e-9);
+
+        let astronomical = EARTH_TEXT
+            .replace("KM-S", "AU-D")
+            .parse::<HorizonsTable>()
+            .expect("");
+        assert_eq!(
+            astronomical.rows[0].1,
+            seconds.rows[0].1 / 1000.0 * ASTRONOMICAL_UNIT
+        );
+    }
+
+    #[test]
+    fn closest_row() {
+        let table = EARTH_TEXT.parse::<HorizonsTable>().expect("");
+        let late = Epoch::from_julian_date(2460311.2, TimeScale::Tdb);
+        assert_eq!(table.state_at(late), table.rows[1]);
+        assert_eq!(table.state_at(Epoch::J2000), table.rows[0]);
+    }
+
+    #[test]
+    fn malformed_tables() {
+        let truncated = EARTH_TEXT.split("$$EOE").next().expect("");
+        assert!(truncated.parse::<HorizonsTable>().is_err());
+
+        let missing_component = EARTH_TEXT.replace(" VZ= 1.623125411049932E-03", "");
+        let error = missing_component.parse::<HorizonsTable>().unwrap_err();
+        assert!(error.starts_with("Incomplete state vector"), "{}", error);
+
+        let empty = format!(
+            "{}$$SOE\n$$EOE",
+            EARTH_TEXT.split("$$SOE").next().expect("")
+        );
+        assert!(empty.parse::<HorizonsTable>().is_err());
+
+        let no_target = EARTH_TEXT.replace("Target body name", "Target");
+        assert!(no_target.parse::<HorizonsTable>().is_err());
+
+        let no_date = EARTH_CSV.replace("2460311.500000000,", "soon,");
+        assert!(no_date.parse::<HorizonsTable>().is_err());
+    }
+}
@@ -0,0 +1,221 @@
+pub mod horizons;
+pub mod tle;
+
+use std::{
+    fs::{create_dir_all, read_to_string, write},
+    path::PathBuf,
+    str::FromStr,
+};
+
+use bevy::{
+    app::App,
+    log::{info, warn, LogPlugin},
+    math::{DQuat, DVec3},
+};
+use serde::Serialize;
+use serde_json::{json, ser::PrettyFormatter, Map, Serializer, Value};
+
+use crate::{
+    physics::epoch::{Epoch, TimeScale},
+    utils::{
+        arguments::ParsedArguments,
+        data::{get_data_dir, DataDir},
+    },
+};
+
+use self::horizons::import_horizons;
+
+/// Obliquity of the ecliptic at J2000 in radians, 84381.448 arcseconds
+const OBLIQUITY_J2000: f64 = 84381.448 / 3600.0 * std::f64::consts::PI / 180.0;
+
+/// Reference plane of imported state vectors
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CelestialFrame {
+    /// Ecliptic and equinox of J2000, the axial tilts of the planet files refer to it
+    #[default]
+    Ecliptic,
+    /// International Celestial Reference Frame, aligned with the mean equator of J2000
+    Icrf,
+}
+impl FromStr for CelestialFrame {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "ecliptic" | "eclip" => Ok(CelestialFrame::Ecliptic),
+            "icrf" | "equatorial" | "frame" => Ok(CelestialFrame::Icrf),
+            _ => Err(format!("Unknown reference frame {}", s)),
+        }
+    }
+}
+impl CelestialFrame {
+    /// Rotates a vector given in this frame into another frame
+    pub fn transform(&self, to: CelestialFrame, vector: DVec3) -> DVec3 {
+        match (self, to) {
+            (CelestialFrame::Ecliptic, CelestialFrame::Icrf) => {
+                DQuat::from_rotation_x(OBLIQUITY_J2000) * vector
+            }
+            (CelestialFrame::Icrf, CelestialFrame::Ecliptic) => {
+                DQuat::from_rotation_x(-OBLIQUITY_J2000) * vector
+            }
+            _ => vector,
+        }
+    }
+}
+
+/// Data file an imported body is written to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BodyKind {
+    Planet,
+    Craft,
+}
+
+/// Physical data found next to the state vectors, used when a new planet file is created
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PhysicalProperties {
+    /// Mass in kg
+    pub mass: Option<f64>,
+    /// Mean radius in m
+    pub radius: Option<f64>,
+    /// Obliquity to the orbit in radians
+    pub axial_tilt: Option<f64>,
+    /// Rotation rate in rad/s
+    pub angular_velocity: Option<f64>,
+}
+
+/// State of a body read from an external source, in SI units
+#[derive(Debug, Clone)]
+pub struct ImportedBody {
+    pub name: String,
+    pub kind: BodyKind,
+    /// Epoch the state vectors are valid at
+    pub epoch: Epoch,
+    pub position: DVec3,
+    pub velocity: DVec3,
+    pub properties: PhysicalProperties,
+}
+
+///
+/// Imports the Horizons tables given on the command line into the data directory.
+/// Only logging is set up, no simulation is started afterwards.
+///
+pub fn run_import(args: &ParsedArguments) {
+    App::new().add_plugins(LogPlugin::default());
+
+    let paths: Vec<PathBuf> = args.horizons.iter().map(PathBuf::from).collect();
+    let center = (!args.import_center.is_empty()).then_some(args.import_center.as_str());
+    let bodies = import_horizons(&paths, center, args.import_frame, args.import_epoch)
+        .expect("Unable to import Horizons tables");
+    let written = write_bodies(&bodies).expect("Unable to write imported bodies");
+    for (body, path) in bodies.iter().zip(written) {
+        info!(
+            "Imported {} at {} into {}",
+            body.name,
+            body.epoch.format(TimeScale::Utc),
+            path.display()
+        );
+    }
+
+    let Some(imported) = bodies.first().map(|b| b.epoch) else {
+        return;
+    };
+    match args.import_epoch {
+        Some(epoch) if (imported.0 - epoch.0).abs() > 1.0 => warn!(
+            "The tables have no state at {}, the imported states are valid at {}",
+            epoch.format(TimeScale::Utc),
+            imported.format(TimeScale::Utc)
+        ),
+        Some(_) => (),
+        None => info!(
+            "Start the simulation with --epoch \"{}\"",
+            imported.format(TimeScale::Utc)
+        ),
+    }
+}
+
+/// Name of the data file without extension, planet textures are looked up with it too
+pub fn file_stem(name: &str) -> String {
+    name.trim()
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+fn file_path(kind: BodyKind, name: &str) -> PathBuf {
+    let dir = match kind {
+        BodyKind::Planet => DataDir::Planets,
+        BodyKind::Craft => DataDir::Crafts,
+    };
+    get_data_dir(dir).join(format!("{}.json", file_stem(name)))
+}
+
+/// Kind of the data file already present for the body, if any
+pub fn existing_kind(name: &str) -> Option<BodyKind> {
+    [BodyKind::Craft, BodyKind::Planet]
+        .into_iter()
+        .find(|kind| file_path(*kind, name).exists())
+}
+
+///
+/// Writes the imported bodies to the planet and craft files of the data directory and returns their paths.
+/// Existing files keep all other fields, only position and velocity are replaced.
+/// The imported states are absolute, so a parent or orbital elements of an existing file are dropped.
+/// New planets need at least a mass and a radius.
+///
+pub fn write_bodies(bodies: &[ImportedBody]) -> Result<Vec<PathBuf>, String> {
+    let mut written = Vec::new();
+    for body in bodies {
+        let path = file_path(body.kind, &body.name);
+        let mut fields = match read_to_string(&path) {
+            Ok(contents) => serde_json::from_str::<Map<String, Value>>(&contents)
+                .map_err(|e| format!("Unable to parse {}: {}", path.display(), e))?,
+            Err(_) => new_fields(body)?,
+        };
+        fields.remove("parent");
+        fields.remove("elements");
+        fields.insert("position".into(), json!(body.position.to_array()));
+        fields.insert("velocity".into(), json!(body.velocity.to_array()));
+
+        // Match the four space indentation of the hand written files
+        let mut contents = Vec::new();
+        let formatter = PrettyFormatter::with_indent(b"    ");
+        Value::Object(fields)
+            .serialize(&mut Serializer::with_formatter(&mut contents, formatter))
+            .expect("");
+
+        create_dir_all(path.parent().expect("")).expect("");
+        write(&path, contents).map_err(|e| format!("Unable to write {}: {}", path.display(), e))?;
+        written.push(path);
+    }
+    Ok(written)
+}
+
+/// Fields of a data file not existing yet
+fn new_fields(body: &ImportedBody) -> Result<Map<String, Value>, String> {
+    let mut fields = Map::new();
+    if body.kind == BodyKind::Craft {
+        return Ok(fields);
+    }
+
+    let properties = &body.properties;
+    let (Some(mass), Some(radius)) = (properties.mass, properties.radius) else {
+        return Err(format!(
+            "No mass and radius known for the new planet {}, create {} first",
+            body.name,
+            file_path(body.kind, &body.name).display()
+        ));
+    };
+    fields.insert("name".into(), json!(body.name));
+    fields.insert("mass".into(), json!(mass));
+    fields.insert("radius".into(), json!(radius));
+    fields.insert(
+        "axial_tilt".into(),
+        json!(properties.axial_tilt.unwrap_or(0.0)),
+    );
+    fields.insert(
+        "angular_velocity".into(),
+        json!(properties.angular_velocity.unwrap_or(0.0)),
+    );
+    Ok(fields)
+}
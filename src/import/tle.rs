@@ -0,0 +1,294 @@
+use std::f64::consts::TAU;
+
+use bevy::math::{DQuat, DVec3};
+
+use crate::physics::epoch::{CalendarDate, Epoch, TimeScale};
+
+const SEC_PER_DAY: f64 = 86400.0;
+
+/// Mean orbital elements of a single Two-Line Element set, angles in radians
+#[derive(Debug, Clone)]
+pub struct TwoLineElements {
+    pub name: String,
+    pub epoch: Epoch,
+    pub inclination: f64,
+    pub right_ascension: f64,
+    pub eccentricity: f64,
+    pub argument_of_perigee: f64,
+    pub mean_anomaly: f64,
+    /// Mean motion in rad/s
+    pub mean_motion: f64,
+}
+impl TwoLineElements {
+    ///
+    /// Position and velocity relative to the earth in its equatorial frame at the given epoch.
+    /// Uses a Keplerian approximation instead of SGP4: the elements are propagated from their epoch
+    /// with the secular drift of node and perigee caused by J2, given with its reference radius.
+    ///
+    pub fn state_at(&self, epoch: Epoch, gm: f64, j2: Option<(f64, f64)>) -> (DVec3, DVec3) {
+        let n = self.mean_motion;
+        let e = self.eccentricity;
+        let a = (gm / (n * n)).cbrt();
+        let dt = epoch.0 - self.epoch.0;
+
+        let (node_rate, perigee_rate) = match j2 {
+            Some((j2, radius)) => {
+                let p = a * (1.0 - e * e);
+                let k = 1.5 * n * j2 * (radius / p).powi(2);
+                let sin_i = self.inclination.sin();
+                (-k * self.inclination.cos(), k * (2.0 - 2.5 * sin_i * sin_i))
+            }
+            None => (0.0, 0.0),
+        };
+        let right_ascension = self.right_ascension + node_rate * dt;
+        let argument_of_perigee = self.argument_of_perigee + perigee_rate * dt;
+        let mean_anomaly = (self.mean_anomaly + n * dt).rem_euclid(TAU);
+
+        // Kepler's equation by Newton iteration
+        let mut eccentric_anomaly = if e < 0.8 {
+            mean_anomaly
+        } else {
+            std::f64::consts::PI
+        };
+        for _ in 0..50 {
+            let delta = (eccentric_anomaly - e * eccentric_anomaly.sin() - mean_anomaly)
+                / (1.0 - e * eccentric_anomaly.cos());
+            eccentric_anomaly -= delta;
+            if delta.abs() < 1e-14 {
+                break;
+            }
+        }
+
+        let (sin_e, cos_e) = eccentric_anomaly.sin_cos();
+        let root = (1.0 - e * e).sqrt();
+        let position = DVec3::new(a * (cos_e - e), a * root * sin_e, 0.0);
+        let velocity = DVec3::new(-sin_e, root * cos_e, 0.0) * (n * a / (1.0 - e * cos_e));
+
+        let rotation = DQuat::from_rotation_z(right_ascension)
+            * DQuat::from_rotation_x(self.inclination)
+            * DQuat::from_rotation_z(argument_of_perigee);
+        (rotation * position, rotation * velocity)
+    }
+}
+
+/// Trimmed text in the given one based, inclusive columns of a TLE line
+fn field(line: &str, start: usize, end: usize, number: usize) -> Result<&str, String> {
+    line.get(start - 1..end)
+        .map(str::trim)
+        .filter(|field| !field.is_empty())
+        .ok_or_else(|| format!("Missing columns {}-{} in line {}", start, end, number))
+}
+
+/// Number in the given one based, inclusive columns of a TLE line
+fn column(line: &str, start: usize, end: usize, number: usize) -> Result<f64, String> {
+    field(line, start, end, number)?
+        .parse::<f64>()
+        .map_err(|_| format!("Invalid columns {}-{} in line {}", start, end, number))
+}
+
+/// Modulo 10 sum of all digits, minus signs count as one
+fn checksum(line: &str) -> u32 {
+    line.chars()
+        .take(68)
+        .map(|c| match c {
+            '-' => 1,
+            _ => c.to_digit(10).unwrap_or(0),
+        })
+        .sum::<u32>()
+        % 10
+}
+
+fn verify_checksum(line: &str, number: usize) -> Result<(), String> {
+    match line.chars().nth(68).and_then(|c| c.to_digit(10)) {
+        Some(expected) if expected != checksum(line) => {
+            Err(format!("Checksum mismatch in line {}", number))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Whether the text contains a line 1 directly followed by a line 2 of an element set
+pub fn is_tle(text: &str) -> bool {
+    let lines: Vec<&str> = text
+        .lines()
+        .map(str::trim_end)
+        .filter(|line| !line.is_empty())
+        .collect();
+    lines
+        .windows(2)
+        .any(|pair| pair[0].starts_with("1 ") && pair[1].starts_with("2 "))
+}
+
+///
+/// Parses all element sets of a TLE file, with or without a name line above each pair of lines.
+/// Entries without a name are named after their catalog number, e.g. NORAD 25544.
+///
+pub fn parse_tle(text: &str) -> Result<Vec<TwoLineElements>, String> {
+    let lines: Vec<(usize, &str)> = text
+        .lines()
+        .enumerate()
+        .map(|(index, line)| (index + 1, line.trim_end()))
+        .filter(|(_, line)| !line.is_empty())
+        .collect();
+
+    let mut elements = Vec::new();
+    let mut index = 0;
+    while index < lines.len() {
+        let mut name = None;
+        if !lines[index].1.starts_with("1 ") {
+            // Three line format, the name may be prefixed by a zero
+            let line = lines[index].1;
+            name = Some(line.strip_prefix("0 ").unwrap_or(line).trim().to_string());
+            index += 1;
+        }
+        let (Some((number1, line1)), Some((number2, line2))) =
+            (lines.get(index).copied(), lines.get(index + 1).copied())
+        else {
+            return Err("Incomplete element set at the end of the file".into());
+        };
+        if !line1.starts_with("1 ") || !line2.starts_with("2 ") {
+            return Err(format!("Expected TLE lines 1 and 2 at line {}", number1));
+        }
+        verify_checksum(line1, number1)?;
+        verify_checksum(line2, number2)?;
+        index += 2;
+
+        // Kept as text, the Alpha-5 scheme replaces the first digit by a letter above 99999
+        let catalog_number = field(line1, 3, 7, number1)?;
+        if !catalog_number.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return Err(format!("Invalid catalog number in line {}", number1));
+        }
+        let year = column(line1, 19, 20, number1)? as i64;
+        let day_of_year = column(line1, 21, 32, number1)?;
+        let year = if year < 57 { 2000 + year } else { 1900 + year };
+        let new_year = CalendarDate {
+            year,
+            month: 1,
+            day: 1,
+            hour: 0,
+            minute: 0,
+            second: 0.0,
+        };
+        // Counted in UTC days, an offset in TDB seconds would drift with the periodic TDB - TT term
+        let new_year_seconds =
+            Epoch::from_calendar(&new_year, TimeScale::Utc).seconds(TimeScale::Utc);
+        let epoch = Epoch::from_seconds(
+            new_year_seconds + (day_of_year - 1.0) * SEC_PER_DAY,
+            TimeScale::Utc,
+        );
+
+        elements.push(TwoLineElements {
+            name: name.unwrap_or_else(|| format!("NORAD {}", catalog_number)),
+            epoch,
+            inclination: column(line2, 9, 16, number2)?.to_radians(),
+            right_ascension: column(line2, 18, 25, number2)?.to_radians(),
+            eccentricity: column(line2, 27, 33, number2)? * 1e-7,
+            argument_of_perigee: column(line2, 35, 42, number2)?.to_radians(),
+            mean_anomaly: column(line2, 44, 51, number2)?.to_radians(),
+            mean_motion: column(line2, 53, 63, number2)? * TAU / SEC_PER_DAY,
+        });
+    }
+    Ok(elements)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ISS: &str = "ISS (ZARYA)
+1 25544U 98067A   08264.51782528 -.00002182  00000-0 -11606-4 0  2927
+2 25544  51.6416 247.4627 0006703 130.5360 325.0288 15.72125391563537
+";
+
+    /// Line with the checksum digit replaced by the correct one
+    fn with_checksum(line: &str) -> String {
+        format!("{}{}", &line[..68], checksum(line))
+    }
+
+    #[test]
+    fn checksums() {
+        let lines: Vec<&str> = ISS.lines().collect();
+        assert_eq!(checksum(lines[1]), 7);
+        assert_eq!(checksum(lines[2]), 7);
+        assert!(verify_checksum(lines[1], 2).is_ok());
+
+        // Minus signs count as one, letters and other characters as zero
+        assert_eq!(checksum("1 -A.7"), 9);
+
+        let corrupted = ISS.replace("51.6416", "51.6417");
+        assert_eq!(
+            parse_tle(&corrupted).unwrap_err(),
+            "Checksum mismatch in line 3"
+        );
+    }
+
+    #[test]
+    fn elements() {
+        let elements = parse_tle(ISS).expect("");
+        assert_eq!(elements.len(), 1);
+        let iss = &elements[0];
+        assert_eq!(iss.name, "ISS (ZARYA)");
+
+        // Day 264.51782528 of the leap year 2008
+        assert_eq!(
+            iss.epoch.calendar(TimeScale::Utc),
+            CalendarDate {
+                year: 2008,
+                month: 9,
+                day: 20,
+                hour: 12,
+                minute: 25,
+                second: 40.104,
+            }
+        );
+        assert_eq!(iss.inclination, 51.6416_f64.to_radians());
+        assert_eq!(iss.right_ascension, 247.4627_f64.to_radians());
+        assert!((iss.eccentricity - 0.0006703).abs() < 1e-15);
+        assert_eq!(iss.argument_of_perigee, 130.536_f64.to_radians());
+        assert_eq!(iss.mean_anomaly, 325.0288_f64.to_radians());
+        assert!((iss.mean_motion - 15.72125391 * TAU / SEC_PER_DAY).abs() < 1e-15);
+
+        // Low earth orbit of about 350 km altitude
+        let (position, velocity) = iss.state_at(iss.epoch, 3.986004418e14, None);
+        assert!((position.length() - 6.72e6).abs() < 1e4);
+        assert!((velocity.length() - 7.7e3).abs() < 50.0);
+    }
+
+    #[test]
+    fn name_lines() {
+        let zero_prefixed = format!("0 {}", ISS);
+        assert_eq!(parse_tle(&zero_prefixed).expect("")[0].name, "ISS (ZARYA)");
+
+        let unnamed: String = ISS.lines().skip(1).map(|l| format!("{}\n", l)).collect();
+        assert_eq!(parse_tle(&unnamed).expect("")[0].name, "NORAD 25544");
+
+        // Alpha-5 catalog numbers are used verbatim
+        let alpha: Vec<String> = unnamed
+            .replace("25544", "A0001")
+            .lines()
+            .map(with_checksum)
+            .collect();
+        let alpha = alpha.join("\n");
+        assert_eq!(parse_tle(&alpha).expect("")[0].name, "NORAD A0001");
+
+        let two_sets = format!("{}{}{}", ISS, unnamed, ISS);
+        let names: Vec<String> = parse_tle(&two_sets)
+            .expect("")
+            .into_iter()
+            .map(|e| e.name)
+            .collect();
+        assert_eq!(names, vec!["ISS (ZARYA)", "NORAD 25544", "ISS (ZARYA)"]);
+    }
+
+    #[test]
+    fn malformed_sets() {
+        let truncated: String = ISS.lines().take(2).collect::<Vec<_>>().join("\n");
+        assert!(parse_tle(&truncated).is_err());
+        assert!(!is_tle(&truncated));
+        assert!(is_tle(ISS));
+        assert!(!is_tle("{ \"position\": [1, 2, 3] }"));
+
+        let swapped: String = ISS.lines().rev().collect::<Vec<_>>().join("\n");
+        assert!(parse_tle(&swapped).is_err());
+    }
+}
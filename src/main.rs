@@ -1,20 +1,13 @@
-use bevy::{
-    app::{App, Startup},
-    core_pipeline::clear_color::ClearColor,
-    ecs::system::Commands,
-    log::info,
-    math::Vec3,
-    pbr::{AmbientLight, DirectionalLight, DirectionalLightBundle},
-    render::color::Color,
-    transform::components::Transform,
-    DefaultPlugins,
-};
+use bevy::{app::App, log::info, DefaultPlugins};
 
 mod floatingorigin;
+mod headless;
+mod import;
 mod objects;
 mod orbits;
 mod physics;
 mod renderer;
+mod save;
 mod ui;
 mod utils;
 
@@ -23,7 +16,13 @@ use orbits::OrbitsPlugins;
 use utils::{arguments::parse_arguments, data::create_data};
 
 use crate::{
-    objects::LoadObjectsPlugins, physics::PhysicPlugin, renderer::RendererPlugin, ui::UiPlugins,
+    headless::{benchmark::run_gravity_benchmark, run_headless},
+    import::run_import,
+    objects::LoadObjectsPlugins,
+    physics::PhysicPlugin,
+    renderer::RendererPlugin,
+    save::{keyframes::KeyframePlugin, SavePlugin},
+    ui::UiPlugins,
 };
 
 fn main() {
@@ -34,36 +33,33 @@ fn main() {
         create_data("data".into());
     }
 
-    App::new()
-        .add_plugins((
-            DefaultPlugins,
-            RendererPlugin,
-            UiPlugins,
-            LoadObjectsPlugins,
-            PhysicPlugin,
-            FloatingOriginPlugin,
-            OrbitsPlugins,
-        ))
-        .add_systems(Startup, spawn_light)
-        .run();
-}
+    if !args.horizons.is_empty() {
+        run_import(&args);
+        return;
+    }
 
-fn spawn_light(mut commands: Commands) {
-    commands.insert_resource(AmbientLight {
-        color: Color::BLACK,
-        brightness: 0.,
-    });
+    if args.benchmark_bodies > 0 {
+        run_gravity_benchmark(args.benchmark_bodies);
+        return;
+    }
 
-    commands.insert_resource(ClearColor(Color::BLACK));
+    if args.headless {
+        run_headless(&args);
+        return;
+    }
 
-    commands.spawn(DirectionalLightBundle {
-        directional_light: DirectionalLight {
-            illuminance: 25000.,
-            shadows_enabled: true,
-            ..Default::default()
-        },
-        transform: Transform::from_xyz(-20000000.0, 0.0, 0.0)
-            .looking_at(Vec3::new(1.0, 0.0, 0.0), Vec3::Z),
-        ..Default::default()
-    });
+    let mut app = App::new();
+    app.add_plugins((
+        DefaultPlugins,
+        RendererPlugin,
+        UiPlugins,
+        LoadObjectsPlugins,
+        PhysicPlugin,
+        FloatingOriginPlugin,
+        OrbitsPlugins,
+        SavePlugin,
+        KeyframePlugin,
+    ));
+    args.insert_resources(&mut app);
+    app.run();
 }
@@ -18,7 +18,7 @@ use crate::{
         planet::components::Planet,
         systemsets::{CameraSets, ObjectSets},
     },
-    physics::systemsets::PhysicsSet,
+    physics::{components::Star, systemsets::PhysicsSet},
     ui::resources::UiClicked,
 };
 
@@ -40,7 +40,8 @@ impl Plugin for SpawnCameraPlugin {
                 (pan_orbit_camera, change_camera_focus)
                     .in_set(CameraSets::MoveCamera)
                     .before(PhysicsSet::All),
-                track_camera_focus
+                (refocus_removed_target, track_camera_focus)
+                    .chain()
                     .in_set(CameraSets::TrackFocus)
                     .after(PhysicsSet::All)
                     .before(FloatingOriginSet::ApplyTransform),
@@ -50,11 +51,11 @@ impl Plugin for SpawnCameraPlugin {
     }
 }
 
-fn spawn_camera(mut commands: Commands, planets: Query<Entity, With<Planet>>) {
+fn spawn_camera(mut commands: Commands, planets: Query<Entity, (With<Planet>, Without<Star>)>) {
     info!("Spawning camera");
     let camera_start_pos = DVec3::new(-20000000.0, 0.0, 0.0);
 
-    // Focus on first parent
+    // Focus on first parent, the start distance would lie within a star
     let mut focus = Entity::PLACEHOLDER;
     for planet in planets.iter() {
         info!("Attached camera to planet {:?}", planet);
@@ -83,6 +84,25 @@ fn spawn_camera(mut commands: Commands, planets: Query<Entity, With<Planet>>) {
     ));
 }
 
+/// Moves the focus to the first planet when the focused entity was despawned, e.g. a destroyed craft
+fn refocus_removed_target(
+    mut camera_q: Query<&mut FocusTarget, With<Camera>>,
+    focus_targets: Query<(), With<Focusable>>,
+    planets: Query<Entity, (With<Planet>, Without<Star>)>,
+) {
+    let mut camera_target = camera_q.single_mut();
+    if focus_targets.contains(camera_target.target) {
+        return;
+    }
+    if let Some(planet) = planets.iter().next() {
+        info!(
+            "Focus target removed, attached camera to planet {:?}",
+            planet
+        );
+        camera_target.target = planet;
+    }
+}
+
 fn track_camera_focus(
     mut camera_q: Query<(&mut FloatingOriginPosition, &FocusTarget), With<Camera>>,
     focus_targets: Query<&FloatingOriginPosition, (With<Focusable>, Without<Camera>)>,
@@ -3,7 +3,8 @@ use bevy::ecs::entity::Entity;
 use {
     bevy::{
         app::{App, Plugin, Startup, Update},
-        asset::{AssetServer, Assets, Handle},
+        asset::Handle,
+        core::Name,
         core_pipeline::core_3d::Camera3d,
         ecs::{
             bundle::Bundle,
@@ -12,8 +13,8 @@ use {
             system::{Commands, Query, Res, ResMut},
         },
         hierarchy::BuildChildren,
-        log::info,
-        math::{DVec3, Vec2, Vec3},
+        log::{info, warn},
+        math::{DQuat, DVec3, Vec2, Vec3},
         pbr::{AlphaMode, PbrBundle, StandardMaterial},
         render::{
             mesh::{shape::Quad, Mesh},
@@ -27,25 +28,49 @@ use {
 
 use crate::{
     floatingorigin::components::FloatingOriginPosition,
-    objects::{components::Focusable, systemsets::ObjectSets},
-    orbits::history::{OrbitHistoryBundle, OrbitHistoryEntity},
-    physics::components::{NBodyAcceleration, NBodyEffector, NBodyVelocity},
-    renderer::line::LineMaterial,
-    utils::{
-        self,
-        data::{get_data_dir, DataDir},
+    import::tle::{is_tle, parse_tle},
+    objects::{
+        components::Focusable,
+        planet::components::Planet,
+        respawn::{BodyDefinitions, RenderAssets, RenderResources},
+        scenario::Scenario,
+        state::StateParser,
+        systemsets::ObjectSets,
     },
+    orbits::{
+        components::OrbitalElements,
+        history::{OrbitHistoryBundle, OrbitHistoryEntity},
+        prediction::{OrbitPredictionBundle, OrbitPredictionEntity},
+    },
+    physics::{
+        components::{
+            Aerodynamics, BurnSchedule, Eclipse, MassG, NBodyAcceleration, NBodyEffector,
+            NBodyVelocity, PrimaryBody, Propulsion, RadiationPressure, ScheduledBurn,
+            ThrustDirection, ZonalHarmonics,
+        },
+        epoch::Epoch,
+        resources::PhysicsElapsed,
+    },
+    utils::data::{get_data_dir, DataDir},
 };
 
 use super::components::{Craft, CraftLabel, FocusType};
 
 #[derive(Bundle)]
 struct CraftBundle {
+    name: Name,
     nbody: NBodyEffector,
     entity_type: Craft,
     position: FloatingOriginPosition,
     velocity: NBodyVelocity,
     acceleration: NBodyAcceleration,
+    primary: PrimaryBody,
+    elements: OrbitalElements,
+    propulsion: Propulsion,
+    burns: BurnSchedule,
+    aerodynamics: Aerodynamics,
+    radiation: RadiationPressure,
+    eclipse: Eclipse,
     focusable: Focusable,
     orbit_history: OrbitHistoryEntity,
     spatial: SpatialBundle,
@@ -63,63 +88,193 @@ pub struct SpawnCraftPlugin;
 
 impl Plugin for SpawnCraftPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Startup, spawn_crafts.in_set(ObjectSets::SpawnCraft))
+        // Crafts from TLE files are placed relative to the earth
+        app.init_resource::<BodyDefinitions>()
+            .add_systems(
+                Startup,
+                spawn_crafts
+                    .in_set(ObjectSets::SpawnCraft)
+                    .after(ObjectSets::SpawnPlanet),
+            )
             .add_systems(Update, orient_labels);
     }
 }
 
+/// Spawns all crafts of the scenario, or from the data directory without one.
+/// JSON files contain a single craft, TLE files (.tle, .txt) one craft per element set around the earth.
+/// Text files without element sets and TLE files which can not be parsed are skipped.
+/// Labels and orbit histories are only created when rendering resources are available.
 fn spawn_crafts(
     mut commands: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<StandardMaterial>>,
-    mut materials_line: ResMut<Assets<LineMaterial>>,
-    asset_server: Res<AssetServer>,
+    render_assets: RenderAssets,
+    planets: Query<(
+        &Planet,
+        &FloatingOriginPosition,
+        &NBodyVelocity,
+        &MassG,
+        Option<&ZonalHarmonics>,
+    )>,
+    elapsed: Res<PhysicsElapsed>,
+    scenario: Option<Res<Scenario>>,
+    mut definitions: ResMut<BodyDefinitions>,
 ) {
-    let proj_dir = get_data_dir(DataDir::Crafts);
-
-    create_dir_all(&proj_dir).expect("");
-    let craft_file_paths = read_dir(&proj_dir).expect("Unable to read craft files");
-
-    for craft_file in craft_file_paths {
-        let craft_file_path = &craft_file.expect("").path();
-
-        let craft_string = read_to_string(craft_file_path).expect("");
-
-        let quad_width = 1.0;
-        let quad_handle = meshes.add(Mesh::from(Quad::new(Vec2::new(quad_width, quad_width))));
-        let texture_handle = asset_server.load("textures/craft.png");
-
-        // this material renders the texture normally
-        let material_handle = materials.add(StandardMaterial {
-            base_color_texture: Some(texture_handle.clone()),
-            alpha_mode: AlphaMode::Blend,
-            unlit: true,
-            ..Default::default()
-        });
-
-        let hist_id = OrbitHistoryBundle::spawn(&mut commands, &mut meshes, &mut materials_line);
-        let _ = commands
-            .spawn(CraftBundle::from_json(&craft_string, hist_id))
-            .with_children(|parent| {
-                parent.spawn(CraftLabelBundle::new(quad_handle, material_handle));
-            })
-            .id();
-
-        info!(
-            "Spawned craft {}",
-            craft_file_path.as_os_str().to_str().expect("")
-        );
+    let mut render = render_assets.resources();
+
+    let sources: Vec<(String, Vec<(String, CraftParser)>)> = match scenario {
+        Some(scenario) => scenario
+            .crafts
+            .iter()
+            .map(|c| (c.name.clone(), vec![(c.name.clone(), c.craft.clone())]))
+            .collect(),
+        None => {
+            let proj_dir = get_data_dir(DataDir::Crafts);
+
+            create_dir_all(&proj_dir).expect("");
+            let craft_file_paths = read_dir(&proj_dir).expect("Unable to read craft files");
+            craft_file_paths
+                .filter_map(|craft_file| {
+                    let craft_file_path = craft_file.expect("").path();
+                    let craft_string = read_to_string(&craft_file_path).expect("");
+                    let craft_name = craft_file_path.file_stem().expect("").to_str().expect("");
+                    let crafts = match craft_file_path.extension().and_then(|e| e.to_str()) {
+                        Some("tle") | Some("txt") => {
+                            if !is_tle(&craft_string) {
+                                warn!("No element sets in {}, skipped", craft_name);
+                                return None;
+                            }
+                            let Some(earth) = planets
+                                .iter()
+                                .find(|p| p.0.name.eq_ignore_ascii_case("earth"))
+                            else {
+                                warn!("No earth to place the crafts of {} around", craft_name);
+                                return None;
+                            };
+                            match tle_crafts(&craft_string, earth, elapsed.start) {
+                                Ok(crafts) => crafts,
+                                Err(e) => {
+                                    warn!("Unable to parse {}, skipped: {}", craft_name, e);
+                                    return None;
+                                }
+                            }
+                        }
+                        _ => vec![(
+                            craft_name.to_owned(),
+                            serde_json::from_str::<CraftParser>(&craft_string).expect(""),
+                        )],
+                    };
+                    Some((craft_file_path.display().to_string(), crafts))
+                })
+                .collect()
+        }
+    };
+
+    for (source, crafts) in sources {
+        for (craft_name, craft) in crafts {
+            let parent = craft.state.parent.as_ref().and_then(|parent| {
+                planets
+                    .iter()
+                    .find(|p| p.0.name.eq_ignore_ascii_case(parent))
+            });
+            let (position, velocity) = craft
+                .state
+                .resolve(
+                    parent.map(|p| (p.1 .0, p.2 .0)),
+                    parent.map_or(0.0, |p| p.3 .0),
+                )
+                .unwrap_or_else(|e| panic!("{}: {}", source, e));
+
+            definitions.crafts.insert(craft_name.clone(), craft.clone());
+            spawn_craft(
+                &mut commands,
+                &mut render,
+                &craft_name,
+                craft,
+                position,
+                velocity,
+            );
+        }
+
+        info!("Spawned craft {}", source);
+    }
+}
+
+/// Spawns a single craft, with its label and lines when rendering
+pub(super) fn spawn_craft(
+    commands: &mut Commands,
+    render: &mut Option<RenderResources>,
+    craft_name: &str,
+    craft: CraftParser,
+    position: DVec3,
+    velocity: DVec3,
+) -> Entity {
+    match render {
+        Some((meshes, materials, materials_line, asset_server)) => {
+            let quad_width = 1.0;
+            let quad_handle = meshes.add(Mesh::from(Quad::new(Vec2::new(quad_width, quad_width))));
+            let texture_handle = asset_server.load("textures/craft.png");
+
+            // this material renders the texture normally
+            let material_handle = materials.add(StandardMaterial {
+                base_color_texture: Some(texture_handle.clone()),
+                alpha_mode: AlphaMode::Blend,
+                unlit: true,
+                ..Default::default()
+            });
+
+            let hist_id = OrbitHistoryBundle::spawn(commands, meshes, materials_line);
+            let prediction_id = OrbitPredictionBundle::spawn(commands, meshes, materials_line);
+            commands
+                .spawn(CraftBundle::from_parser(
+                    craft, craft_name, position, velocity, hist_id,
+                ))
+                .insert(OrbitPredictionEntity(prediction_id))
+                .with_children(|parent| {
+                    parent.spawn(CraftLabelBundle::new(quad_handle, material_handle));
+                })
+                .id()
+        }
+        None => commands
+            .spawn(CraftBundle::from_parser(
+                craft,
+                craft_name,
+                position,
+                velocity,
+                Entity::PLACEHOLDER,
+            ))
+            .id(),
     }
 }
 
+/// Engine, planned burns and surfaces of a craft
+struct CraftProperties {
+    propulsion: Propulsion,
+    burns: Vec<ScheduledBurn>,
+    aerodynamics: Aerodynamics,
+    radiation: RadiationPressure,
+}
+
 impl CraftBundle {
-    fn new(position: DVec3, velocity: DVec3, orbit_history: Entity) -> Self {
+    fn new(
+        name: &str,
+        position: DVec3,
+        velocity: DVec3,
+        properties: CraftProperties,
+        orbit_history: Entity,
+    ) -> Self {
         Self {
+            name: Name::new(name.to_owned()),
             nbody: NBodyEffector,
             entity_type: Craft,
             position: FloatingOriginPosition(position),
             velocity: NBodyVelocity(velocity),
             acceleration: NBodyAcceleration(DVec3::ZERO),
+            primary: PrimaryBody(Entity::PLACEHOLDER),
+            elements: OrbitalElements::default(),
+            propulsion: properties.propulsion,
+            burns: BurnSchedule(properties.burns),
+            aerodynamics: properties.aerodynamics,
+            radiation: properties.radiation,
+            eclipse: Eclipse::default(),
             focusable: Focusable {
                 focus_min_distance: 1000.,
                 focus_sphere_radius: 0.5,
@@ -133,21 +288,130 @@ impl CraftBundle {
         }
     }
 
-    fn from_json(data: &str, orbit_history: Entity) -> Self {
-        let c: CraftParser = serde_json::from_str(data).expect("");
-
+    /// Craft at the absolute position and velocity resolved from the state of the parser
+    fn from_parser(
+        c: CraftParser,
+        name: &str,
+        position: DVec3,
+        velocity: DVec3,
+        orbit_history: Entity,
+    ) -> Self {
         CraftBundle::new(
-            utils::vectors::vec_to_dvec3(&c.position),
-            utils::vectors::vec_to_dvec3(&c.velocity),
+            name,
+            position,
+            velocity,
+            CraftProperties {
+                propulsion: Propulsion {
+                    dry_mass: c.dry_mass,
+                    fuel_mass: c.fuel_mass,
+                    thrust: c.thrust,
+                    isp: c.isp,
+                    throttle: 0.0,
+                    direction: ThrustDirection::default(),
+                    scheduled: false,
+                    fixed_direction: None,
+                    delivered_delta_v: 0.0,
+                },
+                burns: c.burns,
+                aerodynamics: Aerodynamics {
+                    drag_coefficient: c.drag_coefficient,
+                    cross_section: c.cross_section,
+                },
+                radiation: RadiationPressure {
+                    reflectivity: c.reflectivity,
+                    area: c.radiation_area,
+                },
+            },
             orbit_history,
         )
     }
 }
 
-#[derive(Serialize, Deserialize)]
-struct CraftParser {
-    position: Vec<f64>,
-    velocity: Vec<f64>,
+impl CraftParser {
+    /// Craft without engine, drag or radiation pressure
+    fn from_state(position: DVec3, velocity: DVec3) -> Self {
+        CraftParser {
+            state: StateParser::absolute(position, velocity),
+            dry_mass: default_dry_mass(),
+            fuel_mass: 0.0,
+            thrust: 0.0,
+            isp: 0.0,
+            burns: Vec::new(),
+            drag_coefficient: default_drag_coefficient(),
+            cross_section: 0.0,
+            reflectivity: default_reflectivity(),
+            radiation_area: 0.0,
+        }
+    }
+}
+
+/// Crafts of all element sets in a TLE file, propagated to the start epoch around the earth
+fn tle_crafts(
+    data: &str,
+    (planet, position, velocity, mass, harmonics): (
+        &Planet,
+        &FloatingOriginPosition,
+        &NBodyVelocity,
+        &MassG,
+        Option<&ZonalHarmonics>,
+    ),
+    start: Epoch,
+) -> Result<Vec<(String, CraftParser)>, String> {
+    // TLEs refer to the equator, which is tilted like the planet but does not spin
+    let equator = DQuat::from_rotation_x(planet.axial_tilt);
+    let j2 = harmonics.map(|h| (h.j2, h.reference_radius));
+
+    Ok(parse_tle(data)?
+        .into_iter()
+        .map(|elements| {
+            let (r, v) = elements.state_at(start, mass.0, j2);
+            (
+                elements.name,
+                CraftParser::from_state(position.0 + equator * r, velocity.0 + equator * v),
+            )
+        })
+        .collect())
+}
+
+fn default_dry_mass() -> f64 {
+    1000.0
+}
+
+fn default_drag_coefficient() -> f64 {
+    2.2
+}
+
+fn default_reflectivity() -> f64 {
+    1.3
+}
+
+/// Craft file contents. The state may be relative to a parent planet, see StateParser.
+/// Mass and engine are optional, without thrust the craft can not manoeuvre.
+/// Without a cross section the craft is not affected by atmospheric drag.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct CraftParser {
+    #[serde(flatten)]
+    state: StateParser,
+    #[serde(default = "default_dry_mass")]
+    dry_mass: f64,
+    #[serde(default)]
+    fuel_mass: f64,
+    #[serde(default)]
+    thrust: f64,
+    #[serde(default)]
+    isp: f64,
+    #[serde(default)]
+    burns: Vec<ScheduledBurn>,
+    #[serde(default = "default_drag_coefficient")]
+    drag_coefficient: f64,
+    /// Area facing the flow in m^2, crafts without are not affected by drag
+    #[serde(default)]
+    cross_section: f64,
+    #[serde(default = "default_reflectivity")]
+    reflectivity: f64,
+    /// Area facing the star in m^2, crafts without are not affected by radiation pressure
+    #[serde(default)]
+    radiation_area: f64,
 }
 
 impl CraftLabelBundle {
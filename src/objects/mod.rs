@@ -2,16 +2,21 @@ pub mod components;
 pub mod systemsets;
 
 pub mod planet;
+pub mod respawn;
+pub mod scenario;
 
 mod camera;
 mod craft;
+mod state;
 
 use bevy::app::{PluginGroup, PluginGroupBuilder};
 
 use crate::objects::{
     camera::SpawnCameraPlugin, craft::SpawnCraftPlugin, planet::SpawnPlanetsPlugin,
+    scenario::ScenarioViewPlugin,
 };
 
+/// Spawns planets, crafts and the camera with all their visuals, then applies the view of the scenario
 pub struct LoadObjectsPlugins;
 impl PluginGroup for LoadObjectsPlugins {
     fn build(self) -> PluginGroupBuilder {
@@ -19,5 +24,16 @@ impl PluginGroup for LoadObjectsPlugins {
             .add(SpawnPlanetsPlugin)
             .add(SpawnCameraPlugin)
             .add(SpawnCraftPlugin)
+            .add(ScenarioViewPlugin)
+    }
+}
+
+/// Spawns only planets and crafts, without any rendering related components
+pub struct HeadlessObjectsPlugins;
+impl PluginGroup for HeadlessObjectsPlugins {
+    fn build(self) -> PluginGroupBuilder {
+        PluginGroupBuilder::start::<Self>()
+            .add(SpawnPlanetsPlugin)
+            .add(SpawnCraftPlugin)
     }
 }
@@ -1,24 +1,30 @@
 use bevy::{
+    core::Name,
     ecs::{bundle::Bundle, entity::Entity},
     math::{DVec3, Quat, Vec3},
     transform::components::Transform,
 };
 
 use crate::{
-    floatingorigin::bundles::FloatingOriginWithHistoryBundle, objects::components::Focusable,
-    orbits::history::OrbitHistoryEntity, physics::bundles::NBodyActiveBundle, utils,
+    floatingorigin::bundles::FloatingOriginWithHistoryBundle,
+    objects::components::Focusable,
+    orbits::{components::OrbitalElements, history::OrbitHistoryEntity},
+    physics::{bundles::NBodyActiveBundle, components::PrimaryBody},
 };
 
 use super::{super::components::FocusType, components::Planet, parsers::PlanetParser};
 
 #[derive(Bundle)]
 pub struct PlanetBundle {
+    name: Name,
     entity_type: Planet,
     focusable: Focusable,
     transform: Transform,
     orbit_history: OrbitHistoryEntity,
     floating_origin: FloatingOriginWithHistoryBundle,
     nbody: NBodyActiveBundle,
+    primary: PrimaryBody,
+    elements: OrbitalElements,
 }
 impl PlanetBundle {
     fn new(
@@ -32,11 +38,13 @@ impl PlanetBundle {
         orbit_history: Entity,
     ) -> Self {
         Self {
+            name: Name::new(name.clone()),
             entity_type: Planet {
                 name: name,
                 axial_tilt: axial_tilt,
                 spin_velocity: angular_velocity,
                 spin_position: 0.0,
+                radius,
             },
 
             focusable: Focusable {
@@ -52,13 +60,21 @@ impl PlanetBundle {
             orbit_history: OrbitHistoryEntity(orbit_history),
             floating_origin: FloatingOriginWithHistoryBundle::new(&position),
             nbody: NBodyActiveBundle::new(&velocity, mass),
+            primary: PrimaryBody(Entity::PLACEHOLDER),
+            elements: OrbitalElements::default(),
         }
     }
-    pub fn from_parser(parser: PlanetParser, orbit_history: Entity) -> Self {
+    /// Planet at the absolute position and velocity resolved from the state of the parser
+    pub fn from_parser(
+        parser: PlanetParser,
+        position: DVec3,
+        velocity: DVec3,
+        orbit_history: Entity,
+    ) -> Self {
         PlanetBundle::new(
             parser.name,
-            utils::vectors::vec_to_dvec3(&parser.position),
-            utils::vectors::vec_to_dvec3(&parser.velocity),
+            position,
+            velocity,
             parser.mass,
             parser.radius,
             parser.axial_tilt,
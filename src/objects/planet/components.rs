@@ -1,9 +1,27 @@
-use bevy::ecs::component::Component;
+use bevy::{
+    ecs::component::Component,
+    math::{DQuat, DVec3},
+};
 
 #[derive(Component)]
 pub struct Planet {
     pub axial_tilt: f64,
     pub spin_velocity: f64,
+    /// Angle around the spin axis in radians, advanced by every physics step
     pub spin_position: f64,
     pub name: String,
+    /// Radius of the surface sphere in m
+    pub radius: f64,
+}
+
+impl Planet {
+    /// Rotation from the body fixed frame, with the spin axis along z, to the inertial frame
+    pub fn orientation(&self) -> DQuat {
+        DQuat::from_rotation_x(self.axial_tilt) * DQuat::from_rotation_z(self.spin_position)
+    }
+
+    /// Angular velocity vector in the inertial frame, only depends on the tilt and not on the spin reached
+    pub fn angular_velocity(&self) -> DVec3 {
+        DQuat::from_rotation_x(self.axial_tilt) * DVec3::Z * self.spin_velocity
+    }
 }
@@ -1,8 +1,8 @@
 pub mod bundles;
 pub mod components;
+pub mod parsers;
 pub mod spawn;
 
-mod parsers;
 mod rotation;
 
 use bevy::app::Update;
@@ -12,7 +12,14 @@ use bevy::{
     ecs::schedule::{apply_deferred, IntoSystemConfigs},
 };
 
-use self::{rotation::rotate_planets, spawn::spawn_planets};
+use crate::physics::{systemsets::PhysicsSet, PhysicsSchedule};
+
+use super::respawn::BodyDefinitions;
+
+use self::{
+    rotation::{rotate_planets, spin_planets},
+    spawn::spawn_planets,
+};
 
 use super::systemsets::ObjectSets;
 
@@ -20,12 +27,17 @@ pub struct SpawnPlanetsPlugin;
 impl Plugin for SpawnPlanetsPlugin {
     fn build(&self, app: &mut App) {
         // Apply deferred to ensure planets have been created
-        app.add_systems(
-            Startup,
-            (spawn_planets, apply_deferred)
-                .chain()
-                .in_set(ObjectSets::SpawnPlanet),
-        )
-        .add_systems(Update, rotate_planets);
+        app.init_resource::<BodyDefinitions>()
+            .add_systems(
+                Startup,
+                (spawn_planets, apply_deferred)
+                    .chain()
+                    .in_set(ObjectSets::SpawnPlanet),
+            )
+            .add_systems(
+                PhysicsSchedule,
+                spin_planets.in_set(PhysicsSet::Integration),
+            )
+            .add_systems(Update, rotate_planets.after(PhysicsSet::All));
     }
 }
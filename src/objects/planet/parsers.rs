@@ -1,12 +1,53 @@
+use bevy::math::{DQuat, DVec3};
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize)]
+use crate::{
+    objects::state::StateParser,
+    physics::components::{Atmosphere, Star, ZonalHarmonics},
+};
+
+#[derive(Serialize, Deserialize, Clone)]
 pub struct PlanetParser {
     pub name: String,
-    pub position: Vec<f64>,
-    pub velocity: Vec<f64>,
+    #[serde(flatten)]
+    pub state: StateParser,
     pub mass: f64,
     pub radius: f64,
     pub axial_tilt: f64,
     pub angular_velocity: f64,
+    /// Optional zonal harmonic coefficients of an oblate planet
+    #[serde(default)]
+    pub j2: f64,
+    #[serde(default)]
+    pub j3: f64,
+    #[serde(default)]
+    pub j4: f64,
+    /// Radius the harmonic coefficients are normalized to, the planet radius if omitted
+    #[serde(default)]
+    pub reference_radius: Option<f64>,
+    #[serde(default)]
+    pub atmosphere: Option<Atmosphere>,
+    /// Radiated power in W, makes the body a star
+    #[serde(default)]
+    pub luminosity: Option<f64>,
+}
+
+impl PlanetParser {
+    pub fn star(&self) -> Option<Star> {
+        self.luminosity.map(|luminosity| Star { luminosity })
+    }
+
+    /// Zonal harmonics around the spin axis, None for a spherical planet
+    pub fn zonal_harmonics(&self) -> Option<ZonalHarmonics> {
+        if self.j2 == 0.0 && self.j3 == 0.0 && self.j4 == 0.0 {
+            return None;
+        }
+        Some(ZonalHarmonics {
+            j2: self.j2,
+            j3: self.j3,
+            j4: self.j4,
+            reference_radius: self.reference_radius.unwrap_or(self.radius),
+            spin_axis: DQuat::from_rotation_x(self.axial_tilt) * DVec3::Z,
+        })
+    }
 }
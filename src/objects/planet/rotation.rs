@@ -1,30 +1,22 @@
 use std::f64::consts::PI;
 
 use crate::physics::resources::*;
-use bevy::{
-    ecs::system::{Query, Res},
-    math::Quat,
-    time::Time,
-    transform::components::Transform,
-};
+use bevy::{ecs::system::Query, transform::components::Transform};
 
 use super::components::Planet;
 
-pub fn rotate_planets(
-    time: Res<Time>,
-    time_scale: Res<PhysicsTimeScale>,
-    step_scale: Res<PhysicsStepScale>,
-    mut planets_q: Query<(&mut Transform, &mut Planet)>,
-) {
-    for (mut planet_transform, mut planet) in planets_q.iter_mut() {
-        // Compute rotation and condition
-        let speed = time_scale.0 as f64 * step_scale.0 as f64 * time.delta_seconds_f64();
-        planet.spin_position += planet.spin_velocity * speed;
-        let over = (planet.spin_position / (2.0 * PI)).floor();
-        planet.spin_position -= planet.spin_position * over;
+/// Advances the spin of all planets by one physics step, so the surfaces turn with the simulated time
+pub fn spin_planets(step: PhysicsStep, mut planets_q: Query<&mut Planet>) {
+    let step = step.seconds();
+    for mut planet in planets_q.iter_mut() {
+        // Wraps into [0, 2 pi) in both directions of time
+        planet.spin_position =
+            (planet.spin_position + planet.spin_velocity * step).rem_euclid(2.0 * PI);
+    }
+}
 
-        let tilt = Quat::from_rotation_x(planet.axial_tilt as f32);
-        let spin = Quat::from_rotation_z(planet.spin_position as f32);
-        planet_transform.rotation = tilt * spin;
+pub fn rotate_planets(mut planets_q: Query<(&mut Transform, &Planet)>) {
+    for (mut planet_transform, planet) in planets_q.iter_mut() {
+        planet_transform.rotation = planet.orientation().as_f32();
     }
 }
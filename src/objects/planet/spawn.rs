@@ -1,73 +1,125 @@
 use {
     bevy::{
-        asset::{AssetServer, Assets},
-        ecs::system::{Commands, Res, ResMut},
+        ecs::{
+            entity::Entity,
+            system::{Commands, Res, ResMut},
+        },
         log::info,
-        math::Quat,
+        math::{DVec3, Quat},
         pbr::{PbrBundle, StandardMaterial},
-        render::mesh::{shape::UVSphere, Mesh},
+        render::{
+            color::Color,
+            mesh::{shape::UVSphere, Mesh},
+        },
         transform::components::Transform,
     },
-    std::fs::{create_dir_all, read_dir, read_to_string},
+    std::{
+        collections::HashMap,
+        fs::{create_dir_all, read_dir, read_to_string},
+        path::PathBuf,
+    },
 };
 
+use physical_constants::NEWTONIAN_CONSTANT_OF_GRAVITATION;
+
 use crate::{
+    import::file_stem,
+    objects::{
+        respawn::{BodyDefinitions, RenderAssets, RenderResources},
+        scenario::Scenario,
+    },
     orbits::history::{OrbitHistoryBundle, OrbitHistoryEntity},
-    renderer::line::LineMaterial,
     utils::data::{get_data_dir, DataDir},
 };
 
 use super::{bundles::PlanetBundle, parsers::PlanetParser};
 
+/// Color of the emissive material used for stars
+const STAR_COLOR: Color = Color::rgb(1.0, 0.9, 0.7);
+
+/// Spawns all planets of the scenario, or from the data directory without one.
+/// Meshes and orbit histories are only created when rendering resources are available.
 pub fn spawn_planets(
     mut commands: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<StandardMaterial>>,
-    mut materials_line: ResMut<Assets<LineMaterial>>,
-    asset_server: Res<AssetServer>,
+    render_assets: RenderAssets,
+    scenario: Option<Res<Scenario>>,
+    mut definitions: ResMut<BodyDefinitions>,
 ) {
-    let proj_dir = get_data_dir(DataDir::Planets);
+    let mut render = render_assets.resources();
 
-    create_dir_all(&proj_dir).expect("");
-    let planet_file_paths = read_dir(&proj_dir).expect("Unable to read craft files");
+    // Planets of the scenario are named after their texture directory like the files in the data directory
+    let mut pending: Vec<(PathBuf, PlanetParser)> = match scenario {
+        Some(scenario) => scenario
+            .planets
+            .iter()
+            .map(|parser| (PathBuf::from(file_stem(&parser.name)), parser.clone()))
+            .collect(),
+        None => {
+            let proj_dir = get_data_dir(DataDir::Planets);
 
-    for planet_file in planet_file_paths {
-        let planet_file_path = &planet_file.expect("").path();
-        let planet_string = read_to_string(planet_file_path).expect("");
-        let parser: PlanetParser = serde_json::from_str(&planet_string).expect("");
-        let planet_name = planet_file_path.file_stem().expect("").to_str().expect("");
+            create_dir_all(&proj_dir).expect("");
+            let planet_file_paths = read_dir(&proj_dir).expect("Unable to read craft files");
+            planet_file_paths
+                .map(|planet_file| {
+                    let planet_file_path = planet_file.expect("").path();
+                    let planet_string = read_to_string(&planet_file_path).expect("");
+                    let parser = serde_json::from_str(&planet_string).expect("");
+                    (planet_file_path, parser)
+                })
+                .collect()
+        }
+    };
 
-        let mesh_handle = meshes.add(Mesh::from(UVSphere {
-            radius: parser.radius as f32,
-            sectors: 64,
-            stacks: 64,
-        }));
-        let material_handle = materials.add(StandardMaterial {
-            base_color_texture: Some(asset_server.load(format!("{}/base_color.jpg", planet_name))),
-            /*depth_map: Some(asset_server.load(format!("{}/elevation_surface.jpg", planet_name))),
-            parallax_mapping_method: ParallaxMappingMethod::Relief { max_steps: 4 },
-            emissive_texture: Some(asset_server.load(format!("{}/emissive.jpg", planet_name))),
-            emissive: Color::hsl(0.0, 0.0, 0.5),
-            metallic_roughness_texture: Some(
-                asset_server.load(format!("{}/metallic_roughness.png", planet_name)),
-            ),
-            normal_map_texture: Some(asset_server.load(format!("{}/normal_map.jpg", planet_name))),*/
-            ..Default::default()
+    // Resolve planets after their parents, so moons of moons work
+    let mut resolved: HashMap<String, (DVec3, DVec3, f64)> = HashMap::new();
+    let mut ordered = Vec::new();
+    while !pending.is_empty() {
+        let (ready, waiting): (Vec<_>, Vec<_>) = pending.into_iter().partition(|(_, parser)| {
+            parser
+                .state
+                .parent
+                .as_ref()
+                .is_none_or(|parent| resolved.contains_key(&parent.to_lowercase()))
         });
+        if ready.is_empty() {
+            let names: Vec<String> = waiting.into_iter().map(|(_, p)| p.name).collect();
+            panic!("Unknown or circular parents of {}", names.join(", "));
+        }
 
-        let hist_id = OrbitHistoryBundle::spawn(&mut commands, &mut meshes, &mut materials_line);
-        let _ = commands
-            .spawn(PbrBundle {
-                mesh: mesh_handle,
-                material: material_handle,
-                transform: Transform::from_rotation(Quat::from_rotation_x(
-                    parser.axial_tilt as f32,
-                )),
-                ..Default::default()
-            })
-            .insert(OrbitHistoryEntity(hist_id))
-            .insert(PlanetBundle::from_parser(parser, hist_id))
-            .id();
+        for (planet_file_path, parser) in ready {
+            let mass_g = parser.mass * NEWTONIAN_CONSTANT_OF_GRAVITATION;
+            let parent = parser
+                .state
+                .parent
+                .as_ref()
+                .map(|parent| resolved[&parent.to_lowercase()]);
+            let (position, velocity) = parser
+                .state
+                .resolve(
+                    parent.map(|(position, velocity, _)| (position, velocity)),
+                    mass_g + parent.map_or(0.0, |(_, _, parent_g)| parent_g),
+                )
+                .unwrap_or_else(|e| panic!("{}: {}", planet_file_path.display(), e));
+            resolved.insert(parser.name.to_lowercase(), (position, velocity, mass_g));
+            ordered.push((planet_file_path, parser, position, velocity));
+        }
+        pending = waiting;
+    }
+
+    for (planet_file_path, parser, position, velocity) in ordered {
+        let planet_name = planet_file_path.file_stem().expect("").to_str().expect("");
+        definitions.planets.insert(
+            parser.name.clone(),
+            (planet_name.to_owned(), parser.clone()),
+        );
+        spawn_planet(
+            &mut commands,
+            &mut render,
+            planet_name,
+            parser,
+            position,
+            velocity,
+        );
 
         info!(
             "Spawned planet {}",
@@ -75,3 +127,84 @@ pub fn spawn_planets(
         );
     }
 }
+
+/// Spawns a single planet with its texture from the given directory of the assets
+pub fn spawn_planet(
+    commands: &mut Commands,
+    render: &mut Option<RenderResources>,
+    planet_name: &str,
+    mut parser: PlanetParser,
+    position: DVec3,
+    velocity: DVec3,
+) -> Entity {
+    let harmonics = parser.zonal_harmonics();
+    let atmosphere = parser.atmosphere.take();
+    let star = parser.star();
+
+    let planet = match render {
+        Some((meshes, materials, materials_line, asset_server)) => {
+            let mesh_handle = meshes.add(Mesh::from(UVSphere {
+                radius: parser.radius as f32,
+                sectors: 64,
+                stacks: 64,
+            }));
+            // Stars glow on their own and are not lit by themselves
+            let material_handle = match star {
+                Some(_) => materials.add(StandardMaterial {
+                    base_color: STAR_COLOR,
+                    emissive: STAR_COLOR,
+                    unlit: true,
+                    ..Default::default()
+                }),
+                None => materials.add(StandardMaterial {
+                    base_color_texture: Some(
+                        asset_server.load(format!("{}/base_color.jpg", planet_name)),
+                    ),
+                    /*depth_map: Some(asset_server.load(format!("{}/elevation_surface.jpg", planet_name))),
+                    parallax_mapping_method: ParallaxMappingMethod::Relief { max_steps: 4 },
+                    emissive_texture: Some(asset_server.load(format!("{}/emissive.jpg", planet_name))),
+                    emissive: Color::hsl(0.0, 0.0, 0.5),
+                    metallic_roughness_texture: Some(
+                        asset_server.load(format!("{}/metallic_roughness.png", planet_name)),
+                    ),
+                    normal_map_texture: Some(asset_server.load(format!("{}/normal_map.jpg", planet_name))),*/
+                    ..Default::default()
+                }),
+            };
+
+            let hist_id = OrbitHistoryBundle::spawn(commands, meshes, materials_line);
+            commands
+                .spawn(PbrBundle {
+                    mesh: mesh_handle,
+                    material: material_handle,
+                    transform: Transform::from_rotation(Quat::from_rotation_x(
+                        parser.axial_tilt as f32,
+                    )),
+                    ..Default::default()
+                })
+                .insert(OrbitHistoryEntity(hist_id))
+                .insert(PlanetBundle::from_parser(
+                    parser, position, velocity, hist_id,
+                ))
+                .id()
+        }
+        None => commands
+            .spawn(PlanetBundle::from_parser(
+                parser,
+                position,
+                velocity,
+                Entity::PLACEHOLDER,
+            ))
+            .id(),
+    };
+    if let Some(harmonics) = harmonics {
+        commands.entity(planet).insert(harmonics);
+    }
+    if let Some(atmosphere) = atmosphere {
+        commands.entity(planet).insert(atmosphere);
+    }
+    if let Some(star) = star {
+        commands.entity(planet).insert(star);
+    }
+    planet
+}
@@ -0,0 +1,110 @@
+use std::collections::HashMap;
+
+use bevy::{
+    asset::{AssetServer, Assets},
+    ecs::{
+        system::{Commands, In, Res, ResMut, Resource, RunSystemOnce, SystemParam},
+        world::World,
+    },
+    log::info,
+    math::DVec3,
+    pbr::StandardMaterial,
+    render::mesh::Mesh,
+};
+
+use crate::renderer::line::LineMaterial;
+
+use super::{
+    craft::{spawn_craft, CraftParser},
+    planet::{parsers::PlanetParser, spawn::spawn_planet},
+};
+
+/// Assets used for the visuals of planets and crafts, all missing without rendering
+#[derive(SystemParam)]
+pub struct RenderAssets<'w> {
+    meshes: Option<ResMut<'w, Assets<Mesh>>>,
+    materials: Option<ResMut<'w, Assets<StandardMaterial>>>,
+    materials_line: Option<ResMut<'w, Assets<LineMaterial>>>,
+    asset_server: Option<Res<'w, AssetServer>>,
+}
+
+/// Assets of a rendering app, see RenderAssets
+pub type RenderResources<'w> = (
+    ResMut<'w, Assets<Mesh>>,
+    ResMut<'w, Assets<StandardMaterial>>,
+    ResMut<'w, Assets<LineMaterial>>,
+    Res<'w, AssetServer>,
+);
+
+impl<'w> RenderAssets<'w> {
+    /// All assets, or None without rendering
+    pub fn resources(self) -> Option<RenderResources<'w>> {
+        match (
+            self.meshes,
+            self.materials,
+            self.materials_line,
+            self.asset_server,
+        ) {
+            (Some(meshes), Some(materials), Some(materials_line), Some(asset_server)) => {
+                Some((meshes, materials, materials_line, asset_server))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Parsers of all spawned bodies by name, bodies removed by a collision are spawned again from them
+#[derive(Resource, Default)]
+pub struct BodyDefinitions {
+    /// Texture directory and parser of each planet
+    pub planets: HashMap<String, (String, PlanetParser)>,
+    pub crafts: HashMap<String, CraftParser>,
+}
+
+impl BodyDefinitions {
+    pub fn contains(&self, name: &str) -> bool {
+        self.planets.contains_key(name) || self.crafts.contains_key(name)
+    }
+}
+
+///
+/// Spawns the bodies with the given names again from their definitions, at rest in the origin.
+/// Their dynamic state is expected to be restored afterwards, names without a definition are skipped.
+///
+pub fn respawn_bodies(world: &mut World, names: &[String]) {
+    world.run_system_once_with(names.to_vec(), respawn);
+}
+
+fn respawn(
+    In(names): In<Vec<String>>,
+    mut commands: Commands,
+    render_assets: RenderAssets,
+    definitions: Res<BodyDefinitions>,
+) {
+    let mut render = render_assets.resources();
+
+    for name in names {
+        if let Some((texture, parser)) = definitions.planets.get(&name) {
+            spawn_planet(
+                &mut commands,
+                &mut render,
+                texture,
+                parser.clone(),
+                DVec3::ZERO,
+                DVec3::ZERO,
+            );
+        } else if let Some(parser) = definitions.crafts.get(&name) {
+            spawn_craft(
+                &mut commands,
+                &mut render,
+                &name,
+                parser.clone(),
+                DVec3::ZERO,
+                DVec3::ZERO,
+            );
+        } else {
+            continue;
+        }
+        info!("Respawned {}", name);
+    }
+}
@@ -0,0 +1,129 @@
+use std::{fs::read_to_string, str::FromStr};
+
+use bevy::{
+    app::{App, Plugin, Startup},
+    core::Name,
+    ecs::{
+        entity::Entity,
+        query::With,
+        schedule::{apply_deferred, IntoSystemConfigs},
+        system::{Query, Res, ResMut, Resource},
+    },
+    log::{info, warn},
+    render::camera::Camera,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    objects::{
+        components::{FocusTarget, Focusable},
+        craft::CraftParser,
+        planet::{components::Planet, parsers::PlanetParser},
+        systemsets::ObjectSets,
+    },
+    orbits::history::{OrbitHistoryEntity, SelectedReferenceFrame},
+    utils::data::{find_data_file, DataDir},
+};
+
+/// Craft of a scenario, named inline instead of by its file name
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ScenarioCraft {
+    pub name: String,
+    #[serde(flatten)]
+    pub craft: CraftParser,
+}
+
+///
+/// Scenario file contents, replacing the planet and craft directories when selected.
+/// Settings given on the command line take precedence over the ones of the scenario.
+///
+#[derive(Resource, Serialize, Deserialize, Clone, Default)]
+pub struct Scenario {
+    /// Start epoch in any format accepted by --epoch
+    #[serde(default)]
+    pub epoch: Option<String>,
+    #[serde(default)]
+    pub integrator: Option<String>,
+    #[serde(default)]
+    pub timestep: Option<f64>,
+    #[serde(default)]
+    pub time_scale: Option<i32>,
+    /// Name of the planet or craft the camera starts at
+    #[serde(default)]
+    pub focus: Option<String>,
+    /// Name of the planet the orbit histories are drawn relative to
+    #[serde(default)]
+    pub reference_frame: Option<String>,
+    #[serde(default)]
+    pub planets: Vec<PlanetParser>,
+    #[serde(default)]
+    pub crafts: Vec<ScenarioCraft>,
+}
+impl Scenario {
+    /// Parses an optional setting with the same syntax as the command line
+    pub fn setting<T: FromStr<Err = String>>(value: &Option<String>) -> Result<Option<T>, String> {
+        value.as_deref().map(str::parse).transpose()
+    }
+}
+
+///
+/// Reads a scenario from the given path, or by name from the scenario directory of the data directory.
+/// The extension may be omitted for scenarios in the data directory.
+///
+pub fn load_scenario(path: &str) -> Scenario {
+    let scenario_path = find_data_file(DataDir::Scenarios, path);
+    let contents = read_to_string(&scenario_path)
+        .unwrap_or_else(|e| panic!("Unable to read {}: {}", scenario_path.display(), e));
+    let scenario = serde_json::from_str(&contents)
+        .unwrap_or_else(|e| panic!("Unable to parse {}: {}", scenario_path.display(), e));
+    info!("Loaded scenario {}", scenario_path.display());
+    scenario
+}
+
+/// Applies the camera focus and reference frame of the selected scenario
+pub struct ScenarioViewPlugin;
+impl Plugin for ScenarioViewPlugin {
+    fn build(&self, app: &mut App) {
+        // Apply deferred to ensure crafts and camera have been created
+        app.add_systems(
+            Startup,
+            (apply_deferred, apply_scenario_view)
+                .chain()
+                .in_set(ObjectSets::ApplyScenario)
+                .after(ObjectSets::SpawnCraft)
+                .after(ObjectSets::SpawnCamera),
+        );
+    }
+}
+
+fn apply_scenario_view(
+    scenario: Option<Res<Scenario>>,
+    mut camera_q: Query<&mut FocusTarget, With<Camera>>,
+    focusable: Query<(Entity, &Name), With<Focusable>>,
+    planets: Query<(&Planet, &OrbitHistoryEntity)>,
+    mut frame: ResMut<SelectedReferenceFrame>,
+) {
+    let Some(scenario) = scenario else {
+        return;
+    };
+
+    if let Some(focus) = &scenario.focus {
+        match focusable
+            .iter()
+            .find(|(_, name)| name.as_str().eq_ignore_ascii_case(focus))
+        {
+            Some((entity, _)) => camera_q.single_mut().target = entity,
+            None => warn!("No planet or craft {} to focus", focus),
+        }
+    }
+
+    if let Some(reference) = &scenario.reference_frame {
+        match planets
+            .iter()
+            .find(|(planet, _)| planet.name.eq_ignore_ascii_case(reference))
+        {
+            Some((_, history)) => frame.target = history.0,
+            None => warn!("No planet {} to use as reference frame", reference),
+        }
+    }
+}
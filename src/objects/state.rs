@@ -0,0 +1,87 @@
+use bevy::math::DVec3;
+use serde::{Deserialize, Serialize};
+
+use crate::{orbits::components::OrbitalElements, utils};
+
+/// Keplerian elements of an initial state, angles in radians
+#[derive(Serialize, Deserialize, Clone, Copy)]
+pub struct ElementsParser {
+    /// Semi-major axis in m, negative for hyperbolic orbits
+    pub semi_major_axis: f64,
+    #[serde(default)]
+    pub eccentricity: f64,
+    #[serde(default)]
+    pub inclination: f64,
+    #[serde(default)]
+    pub ascending_node: f64,
+    #[serde(default)]
+    pub argument_of_periapsis: f64,
+    #[serde(default)]
+    pub true_anomaly: f64,
+}
+
+///
+/// Initial state of a planet or craft file. Without a parent, position and velocity are absolute.
+/// With a parent, given by its planet name, they are relative to it or replaced by Keplerian elements
+/// around it. The elements refer to the XY plane of the simulation, like the axial tilts.
+///
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct StateParser {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent: Option<String>,
+    #[serde(default)]
+    pub position: Vec<f64>,
+    #[serde(default)]
+    pub velocity: Vec<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub elements: Option<ElementsParser>,
+}
+impl StateParser {
+    pub fn absolute(position: DVec3, velocity: DVec3) -> Self {
+        StateParser {
+            position: position.to_array().to_vec(),
+            velocity: velocity.to_array().to_vec(),
+            ..Default::default()
+        }
+    }
+
+    ///
+    /// Absolute position and velocity, given the absolute state of the parent
+    /// and the gravitational parameter mu of the parent and the body combined.
+    ///
+    pub fn resolve(
+        &self,
+        parent: Option<(DVec3, DVec3)>,
+        mu: f64,
+    ) -> Result<(DVec3, DVec3), String> {
+        let (parent_position, parent_velocity) = match (&self.parent, parent) {
+            (Some(_), Some(parent)) => parent,
+            (Some(name), None) => return Err(format!("Unknown parent {}", name)),
+            (None, _) => (DVec3::ZERO, DVec3::ZERO),
+        };
+
+        let (position, velocity) = match (&self.elements, &self.parent) {
+            (Some(_), None) => return Err("Orbital elements need a parent".into()),
+            (Some(elements), Some(_)) => OrbitalElements {
+                semi_major_axis: elements.semi_major_axis,
+                eccentricity: elements.eccentricity,
+                inclination: elements.inclination,
+                ascending_node: elements.ascending_node,
+                argument_of_periapsis: elements.argument_of_periapsis,
+                true_anomaly: elements.true_anomaly,
+                ..Default::default()
+            }
+            .to_state(mu),
+            (None, _) => {
+                if self.position.len() != 3 || self.velocity.len() != 3 {
+                    return Err("Needs a position and velocity with three components".into());
+                }
+                (
+                    utils::vectors::vec_to_dvec3(&self.position),
+                    utils::vectors::vec_to_dvec3(&self.velocity),
+                )
+            }
+        };
+        Ok((parent_position + position, parent_velocity + velocity))
+    }
+}
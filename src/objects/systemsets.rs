@@ -5,6 +5,7 @@ pub enum ObjectSets {
     SpawnPlanet,
     SpawnCraft,
     SpawnCamera,
+    ApplyScenario,
 }
 
 #[derive(SystemSet, Debug, Hash, PartialEq, Eq, Clone)]
@@ -0,0 +1,18 @@
+use bevy::ecs::component::Component;
+
+///
+/// Osculating Keplerian elements of a body relative to its PrimaryBody.
+/// Angles are in radians, the reference plane is the XY plane with the X axis as reference direction.
+/// For hyperbolic orbits the semi-major axis is negative and the period infinite.
+///
+#[derive(Component, Debug, Clone, Copy, Default, PartialEq)]
+pub struct OrbitalElements {
+    pub semi_major_axis: f64,
+    pub eccentricity: f64,
+    pub inclination: f64,
+    pub ascending_node: f64,
+    pub argument_of_periapsis: f64,
+    pub true_anomaly: f64,
+    pub mean_anomaly: f64,
+    pub period: f64,
+}
@@ -0,0 +1,253 @@
+use std::f64::consts::{PI, TAU};
+
+use bevy::{
+    app::{App, Plugin},
+    ecs::{entity::Entity, schedule::IntoSystemConfigs, system::Query},
+    math::{DQuat, DVec3},
+};
+
+use crate::{
+    floatingorigin::components::FloatingOriginPosition,
+    orbits::{components::OrbitalElements, systemsets::OrbitSets},
+    physics::{
+        components::{MassG, NBodyVelocity, PrimaryBody},
+        systemsets::PhysicsSet,
+        PhysicsSchedule,
+    },
+};
+
+/// Eccentricities and inclinations below this are treated as circular and equatorial
+const ELEMENTS_EPSILON: f64 = 1e-9;
+
+impl OrbitalElements {
+    ///
+    /// Computes the elements from the position and velocity relative to the primary,
+    /// mu is the gravitational parameter of the primary and the body combined.
+    /// Circular orbits have no periapsis, the anomalies are then measured from the ascending node,
+    /// or from the reference direction if the orbit is also equatorial.
+    ///
+    pub fn from_state(position: DVec3, velocity: DVec3, mu: f64) -> Self {
+        let r = position.length();
+        let v = velocity.length();
+
+        let h = position.cross(velocity);
+        let node = DVec3::Z.cross(h);
+        let e_vec = ((v * v - mu / r) * position - position.dot(velocity) * velocity) / mu;
+        let e = e_vec.length();
+
+        let energy = v * v / 2.0 - mu / r;
+        let semi_major_axis = -mu / (2.0 * energy);
+
+        let inclination = (h.z / h.length()).clamp(-1.0, 1.0).acos();
+        let equatorial = node.length() < ELEMENTS_EPSILON * h.length();
+        let circular = e < ELEMENTS_EPSILON;
+
+        // Angle between two vectors, continued over the full circle depending on the sign condition
+        let full_angle = |a: DVec3, b: DVec3, flip: bool| {
+            let angle = (a.dot(b) / (a.length() * b.length()))
+                .clamp(-1.0, 1.0)
+                .acos();
+            if flip {
+                TAU - angle
+            } else {
+                angle
+            }
+        };
+
+        let ascending_node = if equatorial {
+            0.0
+        } else {
+            full_angle(node, DVec3::X, node.y < 0.0)
+        };
+
+        let argument_of_periapsis = match (circular, equatorial) {
+            (true, _) => 0.0,
+            (false, true) => {
+                let angle = e_vec.y.atan2(e_vec.x).rem_euclid(TAU);
+                if h.z < 0.0 {
+                    TAU - angle
+                } else {
+                    angle
+                }
+            }
+            (false, false) => full_angle(node, e_vec, e_vec.z < 0.0),
+        };
+
+        let true_anomaly = match (circular, equatorial) {
+            (false, _) => full_angle(e_vec, position, position.dot(velocity) < 0.0),
+            (true, false) => full_angle(node, position, position.z < 0.0),
+            (true, true) => {
+                let angle = position.y.atan2(position.x).rem_euclid(TAU);
+                if h.z < 0.0 {
+                    TAU - angle
+                } else {
+                    angle
+                }
+            }
+        };
+
+        let half_tan = (true_anomaly / 2.0).tan();
+        let mean_anomaly = if e < 1.0 {
+            let eccentric = 2.0 * (((1.0 - e) / (1.0 + e)).sqrt() * half_tan).atan();
+            (eccentric - e * eccentric.sin()).rem_euclid(TAU)
+        } else if e > 1.0 {
+            let hyperbolic = 2.0 * (((e - 1.0) / (e + 1.0)).sqrt() * half_tan).atanh();
+            e * hyperbolic.sinh() - hyperbolic
+        } else {
+            half_tan + half_tan.powi(3) / 3.0
+        };
+
+        let period = if e < 1.0 {
+            2.0 * PI * (semi_major_axis.powi(3) / mu).sqrt()
+        } else {
+            f64::INFINITY
+        };
+
+        OrbitalElements {
+            semi_major_axis,
+            eccentricity: e,
+            inclination,
+            ascending_node,
+            argument_of_periapsis,
+            true_anomaly,
+            mean_anomaly,
+            period,
+        }
+    }
+
+    ///
+    /// Position and velocity relative to the primary, the inverse of from_state.
+    /// Only the shape, orientation and true anomaly are used, the mean anomaly and period are ignored.
+    /// Parabolic orbits are not supported, their semi-major axis is infinite.
+    ///
+    pub fn to_state(self, mu: f64) -> (DVec3, DVec3) {
+        let e = self.eccentricity;
+        let p = self.semi_major_axis * (1.0 - e * e);
+        let (sin_nu, cos_nu) = self.true_anomaly.sin_cos();
+        let r = p / (1.0 + e * cos_nu);
+
+        let position = DVec3::new(r * cos_nu, r * sin_nu, 0.0);
+        let velocity = DVec3::new(-sin_nu, e + cos_nu, 0.0) * (mu / p).sqrt();
+
+        let rotation = DQuat::from_rotation_z(self.ascending_node)
+            * DQuat::from_rotation_x(self.inclination)
+            * DQuat::from_rotation_z(self.argument_of_periapsis);
+        (rotation * position, rotation * velocity)
+    }
+}
+
+pub struct OrbitalElementsPlugin;
+impl Plugin for OrbitalElementsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            PhysicsSchedule,
+            update_orbital_elements
+                .in_set(OrbitSets::Elements)
+                .after(PhysicsSet::Primary),
+        );
+    }
+}
+
+/// Recomputes the elements of every body with a primary after each physics step
+fn update_orbital_elements(
+    mut bodies: Query<(
+        &FloatingOriginPosition,
+        &NBodyVelocity,
+        &PrimaryBody,
+        Option<&MassG>,
+        &mut OrbitalElements,
+    )>,
+    primaries: Query<(Entity, &FloatingOriginPosition, &NBodyVelocity, &MassG)>,
+) {
+    for (position, velocity, primary, mass, mut elements) in bodies.iter_mut() {
+        let (_, primary_position, primary_velocity, primary_mass) = match primaries.get(primary.0) {
+            Ok(p) => p,
+            Err(_) => continue,
+        };
+
+        *elements = OrbitalElements::from_state(
+            position.0 - primary_position.0,
+            velocity.0 - primary_velocity.0,
+            primary_mass.0 + mass.map_or(0.0, |m| m.0),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::math::DVec3;
+
+    use crate::orbits::components::OrbitalElements;
+
+    /// Gravitational parameter of the Earth in m^3/s^2
+    const MU: f64 = 3.986004418e14;
+
+    /// Converts the state to elements and back, returning the elements
+    fn round_trip(position: DVec3, velocity: DVec3) -> OrbitalElements {
+        let elements = OrbitalElements::from_state(position, velocity, MU);
+        let (new_position, new_velocity) = elements.to_state(MU);
+        assert!((new_position - position).length() <= 1e-9 * position.length());
+        assert!((new_velocity - velocity).length() <= 1e-9 * velocity.length());
+        elements
+    }
+
+    #[test]
+    fn circular_round_trip() {
+        let radius = 7e6;
+        let position = DVec3::new(radius, 0.0, 0.0);
+        let velocity = DVec3::new(0.0, 0.6, 0.8) * (MU / radius).sqrt();
+        let elements = round_trip(position, velocity);
+        assert!(elements.eccentricity < 1e-9);
+        assert!((elements.semi_major_axis - radius).abs() < 1e-6 * radius);
+
+        // Equatorial circular orbits have neither node nor periapsis
+        let elements = round_trip(
+            DVec3::new(0.0, -radius, 0.0),
+            DVec3::X * (MU / radius).sqrt(),
+        );
+        assert!(elements.inclination < 1e-9);
+    }
+
+    #[test]
+    fn elliptical_round_trip() {
+        let position = DVec3::new(6.8e6, 1.2e6, -4e5);
+        let velocity = DVec3::new(-1.5e3, 7.9e3, 2.1e3);
+        let elements = round_trip(position, velocity);
+        assert!(elements.eccentricity > 0.0 && elements.eccentricity < 1.0);
+
+        // Vis-viva equation
+        let energy = velocity.length_squared() / 2.0 - MU / position.length();
+        assert!((elements.semi_major_axis + MU / (2.0 * energy)).abs() < 1e-6);
+
+        // Moving towards the periapsis
+        round_trip(position, -velocity);
+    }
+
+    #[test]
+    fn hyperbolic_round_trip() {
+        let position = DVec3::new(-4e6, 8e6, 3e6);
+        let velocity = DVec3::new(9e3, 4e3, -6e3);
+        let elements = round_trip(position, velocity);
+        assert!(elements.eccentricity > 1.0);
+        assert!(elements.semi_major_axis < 0.0);
+        assert!(elements.period.is_infinite());
+
+        round_trip(position, -velocity);
+    }
+
+    /// Vallado, Fundamentals of Astrodynamics and Applications, example 2-5
+    #[test]
+    fn vallado_example() {
+        let position = DVec3::new(6524.834e3, 6862.875e3, 6448.296e3);
+        let velocity = DVec3::new(4.901327e3, 5.533756e3, -1.976341e3);
+        let elements = round_trip(position, velocity);
+
+        let degrees = |angle: f64| angle.to_degrees();
+        assert!((elements.semi_major_axis - 36127.343e3).abs() < 10.0);
+        assert!((elements.eccentricity - 0.832853).abs() < 1e-6);
+        assert!((degrees(elements.inclination) - 87.870).abs() < 1e-3);
+        assert!((degrees(elements.ascending_node) - 227.898).abs() < 1e-3);
+        assert!((degrees(elements.argument_of_periapsis) - 53.38).abs() < 1e-2);
+        assert!((degrees(elements.true_anomaly) - 92.335).abs() < 1e-3);
+    }
+}
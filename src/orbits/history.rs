@@ -8,7 +8,7 @@ use bevy::{
         component::Component,
         entity::Entity,
         schedule::IntoSystemConfigs,
-        system::{Commands, Query, Res, ResMut, Resource},
+        system::{Commands, Query, Res, ResMut, Resource, SystemParam},
     },
     log::info,
     math::{DVec3, Vec3},
@@ -20,7 +20,7 @@ use bevy::{
 use crate::{
     floatingorigin::{components::FloatingOriginPosition, systemsets::FloatingOriginSet},
     orbits::systemsets::OrbitSets,
-    physics::systemsets::PhysicsSet,
+    physics::{resources::PhysicsElapsed, systemsets::PhysicsSet},
     renderer::line::{LineMaterial, LineStrip, OrbitHistoryMesh},
 };
 
@@ -96,26 +96,48 @@ impl Plugin for OrbitHistoryPlugin {
     }
 }
 
+/// Limits the history updates to one per OrbitHistoryUpdateInterval
+#[derive(SystemParam)]
+struct HistoryUpdateTimer<'w> {
+    last_update: ResMut<'w, OrbitHistoryUpdateInterval>,
+    time: Res<'w, Time<Fixed>>,
+}
+impl HistoryUpdateTimer<'_> {
+    /// Advances the timer, true once the interval has passed
+    fn tick(&mut self) -> bool {
+        self.last_update.since_last += self.time.delta_seconds();
+        if self.last_update.since_last < self.last_update.max_interval {
+            return false;
+        }
+        self.last_update.since_last -= self.last_update.max_interval;
+        true
+    }
+}
+
 fn update_orbit_history(
     mut mesh_asset_mut: ResMut<Assets<Mesh>>,
     history_objects: Query<(&OrbitHistoryEntity, &FloatingOriginPosition)>,
     mut histories: Query<&mut OrbitHistoryMesh>,
     reference: Res<SelectedReferenceFrame>,
     max_length: Res<OrbitHistoryMaxSize>,
-    mut last_update: ResMut<OrbitHistoryUpdateInterval>,
-    time: Res<Time<Fixed>>,
+    mut timer: HistoryUpdateTimer,
+    elapsed: Res<PhysicsElapsed>,
 ) {
-    last_update.since_last += time.delta_seconds();
-    if last_update.since_last < last_update.max_interval {
+    if !timer.tick() {
         return;
-    } else {
-        last_update.since_last -= last_update.max_interval;
     }
 
-    // Add current position to our history
+    // Drop positions after the current time when rewinding, otherwise add the current position to our history
     history_objects.iter().for_each(|(history, origin)| {
         let mut history = histories.get_mut(history.0).expect("");
-        history.history.push_back(origin.0);
+        while history
+            .history
+            .back()
+            .is_some_and(|(time, _)| *time >= elapsed.seconds)
+        {
+            history.history.pop_back();
+        }
+        history.history.push_back((elapsed.seconds, origin.0));
         if history.history.len() > max_length.0 {
             info!("Pruning front: {} {}", history.history.len(), max_length.0);
             history.history.pop_front();
@@ -137,9 +159,11 @@ fn update_orbit_history(
             Some(ref h) => h
                 .iter()
                 .zip(history.history.iter())
-                .map(|(reference, own)| (*own - *reference + *h.back().expect("")).as_vec3())
+                .map(|((_, reference), (_, own))| {
+                    (*own - *reference + h.back().expect("").1).as_vec3()
+                })
                 .collect(),
-            None => history.history.iter().map(|v| v.as_vec3()).collect(),
+            None => history.history.iter().map(|(_, v)| v.as_vec3()).collect(),
         };
 
         // Apply translation
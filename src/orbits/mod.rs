@@ -1,15 +1,22 @@
 use bevy::app::{PluginGroup, PluginGroupBuilder};
 
-use self::history::OrbitHistoryPlugin;
+use self::{
+    elements::OrbitalElementsPlugin, history::OrbitHistoryPlugin, prediction::OrbitPredictionPlugin,
+};
 
 pub mod components;
 pub mod systemsets;
 
+pub mod elements;
 pub mod history;
+pub mod prediction;
 
 pub struct OrbitsPlugins;
 impl PluginGroup for OrbitsPlugins {
     fn build(self) -> PluginGroupBuilder {
-        PluginGroupBuilder::start::<Self>().add(OrbitHistoryPlugin)
+        PluginGroupBuilder::start::<Self>()
+            .add(OrbitHistoryPlugin)
+            .add(OrbitalElementsPlugin)
+            .add(OrbitPredictionPlugin)
     }
 }
@@ -0,0 +1,412 @@
+use std::collections::HashMap;
+
+use bevy::{
+    app::{App, Plugin, Update},
+    asset::Assets,
+    ecs::{
+        bundle::Bundle,
+        component::Component,
+        entity::Entity,
+        query::{Changed, With, Without},
+        removal_detection::RemovedComponents,
+        schedule::IntoSystemConfigs,
+        system::{Commands, Query, Res, ResMut, Resource},
+    },
+    math::{DVec3, Vec3},
+    pbr::MaterialMeshBundle,
+    render::{color::Color, mesh::Mesh, view::NoFrustumCulling},
+    tasks::{block_on, AsyncComputeTaskPool, Task},
+};
+
+use crate::{
+    floatingorigin::{components::FloatingOriginPosition, systemsets::FloatingOriginSet},
+    orbits::{
+        history::{OrbitHistoryEntity, SelectedReferenceFrame},
+        systemsets::OrbitSets,
+    },
+    physics::{
+        components::{
+            Landed, ManeuverNode, MassG, NBodyAcceleration, NBodyEffector, NBodyVelocity,
+            PrimaryBody, ZonalHarmonics,
+        },
+        nbody::GravitySettings,
+        propagation::NBodySnapshot,
+        resources::{IntegratorTolerance, PhysicsElapsed, PhysicsStepScale, PhysicsTimestep},
+        systemsets::PhysicsSet,
+        PhysicsSchedule,
+    },
+    renderer::line::{LineMaterial, LineStrip, OrbitPredictionMesh},
+};
+
+/// Determines how far ahead trajectories are predicted and when the prediction is recomputed
+#[derive(Resource)]
+pub struct PredictionSettings {
+    /// Predicted duration in simulated seconds
+    pub horizon: f64,
+    /// Number of points along each predicted path
+    pub samples: usize,
+    /// Velocity change in m/s by accelerations other than gravity after which the prediction is recomputed
+    pub velocity_tolerance: f64,
+}
+
+/// Predicted positions of all bodies, sampled at a fixed interval starting at the simulated time start
+struct PredictionResult {
+    start: f64,
+    interval: f64,
+    trajectories: HashMap<Entity, Vec<DVec3>>,
+}
+
+/// ManeuverNode applied as an impulse during the prediction
+struct PredictedManeuver {
+    time: f64,
+    craft: usize,
+    primary: Option<usize>,
+    delta_v: DVec3,
+}
+
+/// Latest trajectory prediction and the background task computing the next one
+#[derive(Resource, Default)]
+pub struct OrbitPrediction {
+    pub start: f64,
+    pub interval: f64,
+    pub trajectories: HashMap<Entity, Vec<DVec3>>,
+    /// Velocity change by accelerations other than gravity since the last prediction was started
+    pub deviation: f64,
+    task: Option<Task<PredictionResult>>,
+}
+
+#[derive(Component)]
+pub struct OrbitPredictionEntity(pub Entity);
+
+#[derive(Bundle)]
+pub struct OrbitPredictionBundle {
+    origin: FloatingOriginPosition,
+    mesh: MaterialMeshBundle<LineMaterial>,
+    prediction: OrbitPredictionMesh,
+    culling: NoFrustumCulling,
+}
+impl OrbitPredictionBundle {
+    pub fn spawn(
+        commands: &mut Commands,
+        meshes: &mut ResMut<Assets<Mesh>>,
+        materials_line: &mut ResMut<Assets<LineMaterial>>,
+    ) -> Entity {
+        let line_mesh = meshes.add(Mesh::from(LineStrip { points: vec![] }));
+        let line_mesh_id = line_mesh.id();
+        commands
+            .spawn(OrbitPredictionBundle {
+                origin: FloatingOriginPosition(DVec3::ZERO),
+                mesh: MaterialMeshBundle {
+                    mesh: line_mesh,
+                    material: materials_line.add(LineMaterial { color: Color::CYAN }),
+                    ..Default::default()
+                },
+                prediction: OrbitPredictionMesh {
+                    orbit_mesh: line_mesh_id,
+                },
+                culling: NoFrustumCulling,
+            })
+            .id()
+    }
+}
+
+pub struct OrbitPredictionPlugin;
+impl Plugin for OrbitPredictionPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(PredictionSettings {
+            horizon: 2.0 * 86400.0,
+            samples: 1000,
+            velocity_tolerance: 0.1,
+        })
+        .insert_resource(OrbitPrediction::default())
+        .add_systems(
+            PhysicsSchedule,
+            track_prediction_deviation
+                .in_set(OrbitSets::Prediction)
+                .after(PhysicsSet::Forces)
+                .before(PhysicsSet::Integration),
+        )
+        .add_systems(
+            Update,
+            (update_prediction, draw_predictions)
+                .chain()
+                .in_set(OrbitSets::Prediction)
+                .after(PhysicsSet::All)
+                .before(FloatingOriginSet::ApplyTransform),
+        );
+    }
+}
+
+/// Sums up the velocity change caused by accelerations other than gravity, e.g. thrust
+fn track_prediction_deviation(
+    accelerations: Query<&NBodyAcceleration>,
+    mut prediction: ResMut<OrbitPrediction>,
+    timestep: Res<PhysicsTimestep>,
+    step_scale: Res<PhysicsStepScale>,
+) {
+    let step = timestep.0 * step_scale.0 as f64;
+    let largest = accelerations
+        .iter()
+        .map(|acc| acc.0.length())
+        .fold(0.0, f64::max);
+    prediction.deviation += largest * step;
+}
+
+///
+/// Collects finished predictions and starts a new background prediction when the current one is outdated.
+/// A prediction is outdated once half of its horizon has passed, also while rewinding, a ManeuverNode
+/// was edited or a body changed its velocity by more than the tolerance through accelerations other than gravity.
+/// Maneuver nodes whose burn has not yet started are applied as impulses at the node time.
+///
+#[allow(clippy::type_complexity, clippy::too_many_arguments)]
+fn update_prediction(
+    mut prediction: ResMut<OrbitPrediction>,
+    settings: Res<PredictionSettings>,
+    elapsed: Res<PhysicsElapsed>,
+    tolerance: Res<IntegratorTolerance>,
+    gravity: GravitySettings,
+    bodies: Query<
+        (
+            Entity,
+            &FloatingOriginPosition,
+            &NBodyVelocity,
+            Option<&MassG>,
+            Option<&ZonalHarmonics>,
+        ),
+        (With<NBodyEffector>, Without<Landed>),
+    >,
+    nodes: Query<(Entity, &ManeuverNode, &PrimaryBody)>,
+    changed_nodes: Query<(), Changed<ManeuverNode>>,
+    mut removed_nodes: RemovedComponents<ManeuverNode>,
+) {
+    let nodes_edited = !changed_nodes.is_empty() || removed_nodes.read().count() > 0;
+
+    // Wait for the running prediction
+    if let Some(task) = &prediction.task {
+        if !task.is_finished() {
+            return;
+        }
+        let result = block_on(prediction.task.take().expect(""));
+        prediction.start = result.start;
+        prediction.interval = result.interval;
+        prediction.trajectories = result.trajectories;
+    }
+
+    let outdated = prediction.trajectories.is_empty()
+        || nodes_edited
+        || prediction.deviation > settings.velocity_tolerance
+        || (elapsed.seconds - prediction.start).abs() > settings.horizon / 2.0;
+    if !outdated {
+        return;
+    }
+
+    let mut snapshot = NBodySnapshot::default();
+    snapshot.gravity = gravity.model();
+    for (entity, position, velocity, mass, harmonics) in bodies.iter() {
+        snapshot.push(
+            entity,
+            position.0,
+            velocity.0,
+            mass.map_or(0.0, |m| m.0),
+            harmonics.copied(),
+        );
+    }
+
+    let index_of = |entity: Entity| snapshot.entities.iter().position(|e| *e == entity);
+    let mut maneuvers: Vec<PredictedManeuver> = nodes
+        .iter()
+        .filter(|(_, node, _)| node.burn.is_none())
+        .filter_map(|(entity, node, primary)| {
+            Some(PredictedManeuver {
+                time: node.time,
+                craft: index_of(entity)?,
+                primary: index_of(primary.0),
+                delta_v: node.delta_v,
+            })
+        })
+        .collect();
+    maneuvers.sort_by(|a, b| a.time.total_cmp(&b.time));
+
+    let start = elapsed.seconds;
+    let samples = settings.samples;
+    let interval = settings.horizon / samples as f64;
+    let tolerance = tolerance.0;
+    prediction.deviation = 0.0;
+    prediction.task = Some(AsyncComputeTaskPool::get().spawn(async move {
+        let mut trajectories: Vec<Vec<DVec3>> = snapshot
+            .positions
+            .iter()
+            .map(|p| {
+                let mut trajectory = Vec::with_capacity(samples + 1);
+                trajectory.push(*p);
+                trajectory
+            })
+            .collect();
+
+        let mut maneuvers = maneuvers.into_iter().peekable();
+        for sample in 0..samples {
+            // Stop at every node inside the interval to apply its impulse
+            let mut time = start + sample as f64 * interval;
+            let end = time + interval;
+            while let Some(maneuver) = maneuvers.next_if(|m| m.time < end) {
+                if maneuver.time > time {
+                    snapshot.propagate(maneuver.time - time, tolerance);
+                    time = maneuver.time;
+                }
+                let (rel_position, rel_velocity) = match maneuver.primary {
+                    Some(p) => (
+                        snapshot.positions[maneuver.craft] - snapshot.positions[p],
+                        snapshot.velocities[maneuver.craft] - snapshot.velocities[p],
+                    ),
+                    None => (
+                        snapshot.positions[maneuver.craft],
+                        snapshot.velocities[maneuver.craft],
+                    ),
+                };
+                let node = ManeuverNode {
+                    delta_v: maneuver.delta_v,
+                    ..ManeuverNode::new(maneuver.time)
+                };
+                snapshot.velocities[maneuver.craft] +=
+                    node.inertial_delta_v(rel_position, rel_velocity);
+            }
+            snapshot.propagate(end - time, tolerance);
+            for (trajectory, position) in trajectories.iter_mut().zip(snapshot.positions.iter()) {
+                trajectory.push(*position);
+            }
+        }
+
+        PredictionResult {
+            start,
+            interval,
+            trajectories: snapshot.entities.into_iter().zip(trajectories).collect(),
+        }
+    }));
+}
+
+/// Updates the prediction lines of all crafts, relative to the selected reference frame
+fn draw_predictions(
+    mut mesh_asset_mut: ResMut<Assets<Mesh>>,
+    prediction: Res<OrbitPrediction>,
+    crafts: Query<(Entity, &OrbitPredictionEntity, &FloatingOriginPosition)>,
+    lines: Query<&OrbitPredictionMesh>,
+    frames: Query<(Entity, &OrbitHistoryEntity, &FloatingOriginPosition)>,
+    reference: Res<SelectedReferenceFrame>,
+    elapsed: Res<PhysicsElapsed>,
+) {
+    // Skip samples which already lie in the past
+    let first = if prediction.interval > 0.0 {
+        ((elapsed.seconds - prediction.start) / prediction.interval).ceil() as usize
+    } else {
+        0
+    };
+
+    // Predicted and current position of the reference body
+    let reference_body = frames
+        .iter()
+        .find(|(_, history, _)| history.0 == reference.target)
+        .and_then(|(entity, _, position)| {
+            prediction
+                .trajectories
+                .get(&entity)
+                .map(|trajectory| (trajectory, position.0))
+        });
+
+    for (craft, line, position) in crafts.iter() {
+        let line_mesh = lines.get(line.0).expect("");
+
+        let mut points: Vec<Vec3> = vec![position.0.as_vec3()];
+        if let Some(trajectory) = prediction.trajectories.get(&craft) {
+            let predicted = trajectory.iter().enumerate().skip(first);
+            match reference_body {
+                Some((ref_trajectory, ref_position)) => points.extend(
+                    predicted.map(|(i, own)| (*own - ref_trajectory[i] + ref_position).as_vec3()),
+                ),
+                None => points.extend(predicted.map(|(_, own)| own.as_vec3())),
+            }
+        }
+
+        mesh_asset_mut
+            .get_mut(line_mesh.orbit_mesh)
+            .expect("")
+            .insert_attribute(Mesh::ATTRIBUTE_POSITION, points);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::{ecs::system::RunSystemOnce, MinimalPlugins};
+
+    use crate::physics::{
+        bundles::{NBodyActiveBundle, NBodyPassiveBundle},
+        resources::Integrator,
+        step_physics, PhysicPlugin,
+    };
+
+    use super::*;
+
+    /// Earth and a craft in low orbit, predicted for 100 samples one minute apart
+    fn simulation() -> (App, Entity) {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, PhysicPlugin))
+            .insert_resource(Integrator::DormandPrince45)
+            .insert_resource(PhysicsTimestep(60.0))
+            .insert_resource(PredictionSettings {
+                horizon: 6000.0,
+                samples: 100,
+                velocity_tolerance: 0.1,
+            })
+            .insert_resource(OrbitPrediction::default());
+        app.world.spawn((
+            NBodyActiveBundle::new(&DVec3::ZERO, 5.972e24),
+            FloatingOriginPosition(DVec3::ZERO),
+        ));
+        let craft = app
+            .world
+            .spawn((
+                NBodyPassiveBundle::new(&DVec3::new(0.0, 7000.0, 2000.0)),
+                FloatingOriginPosition(DVec3::new(7e6, 0.0, 0.0)),
+            ))
+            .id();
+        (app, craft)
+    }
+
+    /// Runs the prediction system until the background prediction has finished
+    fn finish_prediction(app: &mut App) {
+        app.world.run_system_once(update_prediction);
+        while app.world.resource::<OrbitPrediction>().task.is_some() {
+            std::thread::yield_now();
+            app.world.run_system_once(update_prediction);
+        }
+    }
+
+    #[test]
+    fn prediction_matches_physics() {
+        let (mut app, craft) = simulation();
+        finish_prediction(&mut app);
+        let trajectory = app.world.resource::<OrbitPrediction>().trajectories[&craft].clone();
+        assert_eq!(trajectory.len(), 101);
+
+        for predicted in trajectory.iter().skip(1) {
+            step_physics(&mut app.world);
+            let position = app.world.get::<FloatingOriginPosition>(craft).expect("").0;
+            assert!((position - *predicted).length() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn deviation_restarts_prediction() {
+        let (mut app, _) = simulation();
+        finish_prediction(&mut app);
+
+        // Still up to date, until the velocity changed by more than the tolerance
+        app.world.run_system_once(update_prediction);
+        assert!(app.world.resource::<OrbitPrediction>().task.is_none());
+
+        app.world.resource_mut::<OrbitPrediction>().deviation = 0.2;
+        app.world.run_system_once(update_prediction);
+        let prediction = app.world.resource::<OrbitPrediction>();
+        assert!(prediction.task.is_some());
+        assert_eq!(prediction.deviation, 0.0);
+    }
+}
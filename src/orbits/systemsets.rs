@@ -3,4 +3,6 @@ use bevy::ecs::schedule::SystemSet;
 #[derive(SystemSet, Debug, Hash, PartialEq, Eq, Clone)]
 pub enum OrbitSets {
     DrawHistory,
+    Elements,
+    Prediction,
 }
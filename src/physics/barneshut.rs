@@ -0,0 +1,271 @@
+use bevy::math::DVec3;
+
+/// Maximum subdivision depth, bodies at almost the same position share a leaf below it
+const MAX_DEPTH: u32 = 48;
+/// Marks a missing child or body
+const NONE: u32 = u32::MAX;
+
+/// Cube of the octree, leafs hold their bodies while inner nodes sum up their children
+struct OctreeNode {
+    center: DVec3,
+    half_size: f64,
+    mass: f64,
+    center_of_mass: DVec3,
+    children: [u32; 8],
+    /// First body of the leaf, further bodies at the maximum depth are chained by Octree::next
+    body: u32,
+}
+
+impl OctreeNode {
+    fn new(center: DVec3, half_size: f64) -> Self {
+        OctreeNode {
+            center,
+            half_size,
+            mass: 0.0,
+            center_of_mass: DVec3::ZERO,
+            children: [NONE; 8],
+            body: NONE,
+        }
+    }
+
+    fn is_leaf(&self) -> bool {
+        self.children.iter().all(|c| *c == NONE)
+    }
+
+    fn contains(&self, position: DVec3) -> bool {
+        (position - self.center).abs().max_element() <= self.half_size
+    }
+
+    fn octant(&self, position: DVec3) -> usize {
+        (position.x >= self.center.x) as usize
+            | ((position.y >= self.center.y) as usize) << 1
+            | ((position.z >= self.center.z) as usize) << 2
+    }
+}
+
+/// Octree over all attracting bodies, stored as a flat list of nodes with the root at index 0
+pub struct Octree {
+    nodes: Vec<OctreeNode>,
+    /// Next body in the same leaf for every body
+    next: Vec<u32>,
+    positions: Vec<DVec3>,
+    masses: Vec<f64>,
+}
+
+impl Octree {
+    /// Builds the tree from all bodies with a non-zero mass
+    pub fn build(positions: &[DVec3], masses: &[f64]) -> Self {
+        let (min, max) = positions
+            .iter()
+            .zip(masses)
+            .filter(|(_, m)| **m != 0.0)
+            .fold((DVec3::MAX, DVec3::MIN), |(min, max), (p, _)| {
+                (min.min(*p), max.max(*p))
+            });
+
+        let mut tree = Octree {
+            nodes: Vec::new(),
+            next: vec![NONE; positions.len()],
+            positions: positions.to_vec(),
+            masses: masses.to_vec(),
+        };
+        if min.x > max.x {
+            return tree;
+        }
+        let half_size = ((max - min).max_element() / 2.0).max(1.0);
+        tree.nodes
+            .push(OctreeNode::new((min + max) / 2.0, half_size));
+
+        for (body, mass) in masses.iter().enumerate() {
+            if *mass != 0.0 {
+                tree.insert(0, body as u32, 0);
+            }
+        }
+        tree
+    }
+
+    fn insert(&mut self, node: usize, body: u32, depth: u32) {
+        let position = self.positions[body as usize];
+        let mass = self.masses[body as usize];
+
+        let n = &mut self.nodes[node];
+        n.center_of_mass = (n.center_of_mass * n.mass + position * mass) / (n.mass + mass);
+        n.mass += mass;
+
+        if n.is_leaf() {
+            // Empty leaf or maximum depth reached, store the body here
+            if n.body == NONE || depth >= MAX_DEPTH {
+                self.next[body as usize] = n.body;
+                n.body = body;
+                return;
+            }
+
+            // Occupied leaf, push its body down
+            let existing = std::mem::replace(&mut n.body, NONE);
+            let child = self.child(node, self.positions[existing as usize]);
+            self.insert(child, existing, depth + 1);
+        }
+
+        let child = self.child(node, position);
+        self.insert(child, body, depth + 1);
+    }
+
+    /// Index of the child containing position, creating it if necessary
+    fn child(&mut self, node: usize, position: DVec3) -> usize {
+        let octant = self.nodes[node].octant(position);
+        if self.nodes[node].children[octant] == NONE {
+            let parent = &self.nodes[node];
+            let half_size = parent.half_size / 2.0;
+            let offset = DVec3::new(
+                if octant & 1 != 0 {
+                    half_size
+                } else {
+                    -half_size
+                },
+                if octant & 2 != 0 {
+                    half_size
+                } else {
+                    -half_size
+                },
+                if octant & 4 != 0 {
+                    half_size
+                } else {
+                    -half_size
+                },
+            );
+            let center = parent.center + offset;
+            self.nodes.push(OctreeNode::new(center, half_size));
+            let index = (self.nodes.len() - 1) as u32;
+            self.nodes[node].children[octant] = index;
+        }
+        self.nodes[node].children[octant] as usize
+    }
+
+    ///
+    /// Gravitational acceleration at the position of body, which is excluded from the sum.
+    /// Nodes appearing smaller than the opening angle, their size divided by the distance,
+    /// are approximated by a point mass at their center of mass. Nodes containing the position
+    /// are always opened and the bodies of a leaf are summed individually.
+    ///
+    pub fn acceleration(
+        &self,
+        body: usize,
+        position: DVec3,
+        opening_angle: f64,
+        softening: f64,
+    ) -> DVec3 {
+        let mut acc = DVec3::ZERO;
+        if self.nodes.is_empty() {
+            return acc;
+        }
+
+        let softening_sq = softening * softening;
+        let point_mass_acc = |other_position: DVec3, other_mass: f64| {
+            let dist_vec = other_position - position;
+            let dist_sq = dist_vec.length_squared() + softening_sq;
+            if dist_sq > 0.0 {
+                (other_mass / (dist_sq * dist_sq.sqrt())) * dist_vec
+            } else {
+                DVec3::ZERO
+            }
+        };
+
+        let mut stack = vec![0usize];
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            if node.mass == 0.0 {
+                continue;
+            }
+
+            if node.is_leaf() {
+                let mut other = node.body;
+                while other != NONE {
+                    if other as usize != body {
+                        acc += point_mass_acc(
+                            self.positions[other as usize],
+                            self.masses[other as usize],
+                        );
+                    }
+                    other = self.next[other as usize];
+                }
+                continue;
+            }
+
+            let dist_sq = (node.center_of_mass - position).length_squared();
+            let size = 2.0 * node.half_size;
+            if !node.contains(position) && size * size < opening_angle * opening_angle * dist_sq {
+                acc += point_mass_acc(node.center_of_mass, node.mass);
+            } else {
+                stack.extend(
+                    node.children
+                        .iter()
+                        .filter(|c| **c != NONE)
+                        .map(|c| *c as usize),
+                );
+            }
+        }
+        acc
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::math::DVec3;
+
+    use crate::{
+        headless::benchmark::asteroid_belt,
+        physics::{
+            nbody::GravityModel,
+            resources::{GravityParallelism, GravitySolver},
+        },
+    };
+
+    /// Maximum and rms acceleration error of the Barnes-Hut solver relative to the direct sum
+    fn relative_errors(positions: &[DVec3], masses: &[f64], opening_angle: f64) -> (f64, f64) {
+        let direct = GravityModel {
+            parallelism: GravityParallelism::Serial,
+            ..Default::default()
+        };
+        let barnes_hut = GravityModel {
+            solver: GravitySolver::BarnesHut,
+            opening_angle,
+            ..direct
+        };
+
+        let mut exact = vec![DVec3::ZERO; positions.len()];
+        let mut approximated = vec![DVec3::ZERO; positions.len()];
+        direct.accelerations(positions, masses, &mut exact);
+        barnes_hut.accelerations(positions, masses, &mut approximated);
+        let errors: Vec<f64> = exact
+            .iter()
+            .zip(approximated)
+            .map(|(e, a)| (a - *e).length() / e.length())
+            .collect();
+        (
+            errors.iter().cloned().fold(0.0, f64::max),
+            (errors.iter().map(|e| e * e).sum::<f64>() / errors.len() as f64).sqrt(),
+        )
+    }
+
+    #[test]
+    fn accuracy_against_direct_sum() {
+        let (positions, masses) = asteroid_belt(2000);
+        let (max, rms) = relative_errors(&positions, &masses, 0.5);
+        assert!(max < 0.15 && rms < 5e-3, "max {} rms {}", max, rms);
+
+        // Opening every node is an exact direct sum
+        assert!(relative_errors(&positions, &masses, 0.0).0 < 1e-12);
+    }
+
+    #[test]
+    fn bodies_sharing_a_leaf() {
+        // The first two bodies are closer than the smallest node and end up in the same leaf
+        let positions = [
+            DVec3::ZERO,
+            DVec3::new(1e-6, 0.0, 0.0),
+            DVec3::new(1e11, 2e10, 0.0),
+        ];
+        let masses = [1e10, 3e10, 1e20];
+        assert!(relative_errors(&positions, &masses, 0.5).0 < 1e-12);
+    }
+}
@@ -0,0 +1,576 @@
+use bevy::{
+    asset::{Assets, Handle},
+    core::Name,
+    ecs::{
+        entity::Entity,
+        event::{EventReader, EventWriter},
+        query::{With, Without},
+        system::{Commands, Query, Res, ResMut},
+    },
+    hierarchy::DespawnRecursiveExt,
+    log::info,
+    math::DVec3,
+    render::mesh::{shape::UVSphere, Mesh},
+};
+
+use crate::{
+    floatingorigin::components::FloatingOriginPosition,
+    objects::{
+        components::{Craft, Focusable},
+        planet::components::Planet,
+    },
+    orbits::{
+        history::{OrbitHistoryEntity, SelectedReferenceFrame},
+        prediction::OrbitPredictionEntity,
+    },
+};
+
+use super::{
+    components::{Landed, MassG, NBodyVelocity},
+    events::ImpactEvent,
+    resources::{BodyCollisionPolicy, ImpactPolicy, PhysicsElapsed, PhysicsStep},
+};
+
+///
+/// Fraction of the segment from start to end at which it first enters a sphere around the origin.
+/// Returns 0 if the start already lies inside and None if the segment misses the sphere.
+///
+fn segment_sphere_entry(start: DVec3, end: DVec3, radius: f64) -> Option<f64> {
+    let c = start.length_squared() - radius * radius;
+    if c <= 0.0 {
+        return Some(0.0);
+    }
+
+    let d = end - start;
+    let a = d.length_squared();
+    if a == 0.0 {
+        return None;
+    }
+    let b = 2.0 * start.dot(d);
+    let discriminant = b * b - 4.0 * a * c;
+    if discriminant < 0.0 {
+        return None;
+    }
+
+    let s = (-b - discriminant.sqrt()) / (2.0 * a);
+    (0.0..=1.0).contains(&s).then_some(s)
+}
+
+///
+/// Handles planets touching each other during the last step according to the BodyCollisionPolicy.
+/// Merging keeps the heavier planet, combining mass, momentum and volume, and despawns the lighter one
+/// together with its orbit history. Crafts landed on the removed planet are moved to the merged one.
+///
+#[allow(clippy::type_complexity, clippy::too_many_arguments)]
+pub fn collide_bodies(
+    mut commands: Commands,
+    mut planets: Query<
+        (
+            Entity,
+            &mut Planet,
+            &mut FloatingOriginPosition,
+            &mut NBodyVelocity,
+            &mut MassG,
+            &mut Focusable,
+            Option<&Handle<Mesh>>,
+            Option<&OrbitHistoryEntity>,
+        ),
+        Without<Craft>,
+    >,
+    mut landed: Query<(&mut Landed, &FloatingOriginPosition), With<Craft>>,
+    mut meshes: Option<ResMut<Assets<Mesh>>>,
+    mut reference: Option<ResMut<SelectedReferenceFrame>>,
+    policy: Res<BodyCollisionPolicy>,
+    step: PhysicsStep,
+) {
+    if *policy == BodyCollisionPolicy::Soften {
+        return;
+    }
+    let step = step.seconds();
+
+    let entities: Vec<Entity> = planets.iter().map(|p| p.0).collect();
+    let mut merges: Vec<(Entity, Entity)> = Vec::new();
+    for (i, first) in entities.iter().enumerate() {
+        for second in entities.iter().skip(i + 1) {
+            if merges
+                .iter()
+                .any(|(absorbed, _)| absorbed == first || absorbed == second)
+            {
+                continue;
+            }
+
+            // Order by mass, the heavier planet survives a merge
+            let [mut heavy, mut light] = planets.get_many_mut([*first, *second]).expect("");
+            if heavy.4 .0 < light.4 .0 {
+                std::mem::swap(&mut heavy, &mut light);
+            }
+            let (
+                a,
+                mut a_planet,
+                mut a_position,
+                mut a_velocity,
+                mut a_mass,
+                mut a_focus,
+                a_mesh,
+                a_history,
+            ) = heavy;
+            let (b, b_planet, mut b_position, mut b_velocity, b_mass, _, _, b_history) = light;
+
+            let end = b_position.0 - a_position.0;
+            let rel_velocity = b_velocity.0 - a_velocity.0;
+            let contact = a_planet.radius + b_planet.radius;
+            if segment_sphere_entry(end - rel_velocity * step, end, contact).is_none() {
+                continue;
+            }
+            let total = a_mass.0 + b_mass.0;
+
+            match *policy {
+                BodyCollisionPolicy::Merge => {
+                    a_position.0 = (a_position.0 * a_mass.0 + b_position.0 * b_mass.0) / total;
+                    a_velocity.0 = (a_velocity.0 * a_mass.0 + b_velocity.0 * b_mass.0) / total;
+                    a_mass.0 = total;
+
+                    let radius = (a_planet.radius.powi(3) + b_planet.radius.powi(3)).cbrt();
+                    a_planet.radius = radius;
+                    a_focus.focus_sphere_radius = radius;
+                    a_focus.focus_min_distance = radius * 1.006;
+                    if let (Some(meshes), Some(mesh)) = (meshes.as_mut(), a_mesh) {
+                        meshes.insert(
+                            mesh.id(),
+                            Mesh::from(UVSphere {
+                                radius: radius as f32,
+                                sectors: 64,
+                                stacks: 64,
+                            }),
+                        );
+                    }
+
+                    // Drop the orbit history of the removed planet, keep the reference frame valid
+                    if let (Some(own), Some(other)) = (a_history, b_history) {
+                        if let Some(reference) = reference.as_mut() {
+                            if reference.target == other.0 {
+                                reference.target = own.0;
+                            }
+                        }
+                        if other.0 != Entity::PLACEHOLDER {
+                            commands.entity(other.0).despawn_recursive();
+                        }
+                    }
+
+                    info!("{} merged into {}", b_planet.name, a_planet.name);
+                    commands.entity(b).despawn_recursive();
+                    merges.push((b, a));
+                }
+                BodyCollisionPolicy::Bounce => {
+                    // Coinciding centers have no connecting line, push apart along the motion instead
+                    let normal = end
+                        .try_normalize()
+                        .or_else(|| (-rel_velocity).try_normalize())
+                        .unwrap_or(DVec3::X);
+                    let approach = rel_velocity.dot(normal);
+                    if approach < 0.0 {
+                        a_velocity.0 += normal * (2.0 * b_mass.0 / total * approach);
+                        b_velocity.0 -= normal * (2.0 * a_mass.0 / total * approach);
+                    }
+
+                    // Separate overlapping bodies around their center of mass
+                    let overlap = contact - end.length();
+                    if overlap > 0.0 {
+                        a_position.0 -= normal * (overlap * b_mass.0 / total);
+                        b_position.0 += normal * (overlap * a_mass.0 / total);
+                    }
+                }
+                BodyCollisionPolicy::Soften => (),
+            }
+        }
+    }
+
+    // Move crafts resting on a removed planet onto the surface of the merged one
+    for (absorbed, survivor) in merges {
+        let (_, planet, position, ..) = planets.get(survivor).expect("");
+        for (mut landed, craft_position) in landed.iter_mut() {
+            if landed.planet == absorbed {
+                let surface = (craft_position.0 - position.0).normalize() * planet.radius;
+                landed.planet = survivor;
+                landed.surface = planet.orientation().inverse() * surface;
+            }
+        }
+    }
+}
+
+///
+/// Detects crafts hitting a planet surface during the last step.
+/// The motion relative to each planet is swept linearly over the step, so fast crafts can not tunnel
+/// through a planet between two steps. Depending on the ImpactPolicy the craft lands or is despawned.
+/// Impacts only happen forwards in time, rewinding lifts landed crafts off again, see lift_off.
+///
+#[allow(clippy::type_complexity)]
+pub fn detect_impacts(
+    mut commands: Commands,
+    mut crafts: Query<
+        (
+            Entity,
+            &mut FloatingOriginPosition,
+            &mut NBodyVelocity,
+            Option<&OrbitHistoryEntity>,
+            Option<&OrbitPredictionEntity>,
+        ),
+        (With<Craft>, Without<Landed>, Without<Planet>),
+    >,
+    planets: Query<
+        (
+            Entity,
+            &Planet,
+            &FloatingOriginPosition,
+            &NBodyVelocity,
+            &MassG,
+        ),
+        Without<Craft>,
+    >,
+    mut impacts: EventWriter<ImpactEvent>,
+    policy: Res<ImpactPolicy>,
+    elapsed: Res<PhysicsElapsed>,
+    step: PhysicsStep,
+) {
+    let step = step.seconds();
+    if step < 0.0 {
+        return;
+    }
+
+    for (craft, mut position, mut velocity, history, prediction) in crafts.iter_mut() {
+        // Earliest impact of all planets during this step
+        let impact = planets
+            .iter()
+            .filter_map(|(planet, planet_c, p_position, p_velocity, mass)| {
+                let rel_velocity = velocity.0 - p_velocity.0;
+                let end = position.0 - p_position.0;
+                let start = end - rel_velocity * step;
+                segment_sphere_entry(start, end, planet_c.radius).map(|s| {
+                    let surface = (start + (end - start) * s).normalize() * planet_c.radius;
+                    // Take back the velocity gained from the surface gravity after the impact
+                    let rel_velocity = rel_velocity
+                        + surface * mass.0 / planet_c.radius.powi(3) * (1.0 - s) * step;
+                    (
+                        s,
+                        planet,
+                        planet_c,
+                        p_position.0,
+                        p_velocity.0,
+                        surface,
+                        rel_velocity,
+                    )
+                })
+            })
+            .min_by(|a, b| a.0.total_cmp(&b.0));
+
+        let Some((s, planet, planet_c, p_position, p_velocity, surface, rel_velocity)) = impact
+        else {
+            continue;
+        };
+
+        // Surface point in the body fixed frame of the planet
+        let fixed = planet_c.orientation().inverse() * surface;
+        impacts.send(ImpactEvent {
+            craft,
+            planet,
+            latitude: (fixed.z / planet_c.radius).clamp(-1.0, 1.0).asin(),
+            longitude: fixed.y.atan2(fixed.x),
+            speed: rel_velocity.length(),
+        });
+
+        match *policy {
+            ImpactPolicy::Land => {
+                position.0 = p_position + surface;
+                velocity.0 = p_velocity;
+                commands.entity(craft).insert(Landed {
+                    planet,
+                    surface: fixed,
+                    time: elapsed.seconds + step * s,
+                    velocity: rel_velocity,
+                });
+            }
+            ImpactPolicy::Destroy => {
+                // Remove the lines drawn for the craft as well
+                for line in [history.map(|h| h.0), prediction.map(|p| p.0)]
+                    .into_iter()
+                    .flatten()
+                    .filter(|e| *e != Entity::PLACEHOLDER)
+                {
+                    commands.entity(line).despawn_recursive();
+                }
+                commands.entity(craft).despawn_recursive();
+            }
+        }
+    }
+}
+
+/// Keeps landed crafts on their spot of the rotating planet surface
+pub fn follow_surface(
+    mut crafts: Query<(&Landed, &mut FloatingOriginPosition, &mut NBodyVelocity), Without<Planet>>,
+    planets: Query<(&Planet, &FloatingOriginPosition, &NBodyVelocity)>,
+) {
+    for (landed, mut position, mut velocity) in crafts.iter_mut() {
+        let Ok((planet, p_position, p_velocity)) = planets.get(landed.planet) else {
+            continue;
+        };
+        let orientation = planet.orientation();
+        let surface = orientation * landed.surface;
+        let spin_axis = orientation * DVec3::Z;
+        position.0 = p_position.0 + surface;
+        velocity.0 = p_velocity.0 + spin_axis.cross(surface) * planet.spin_velocity;
+    }
+}
+
+pub fn log_impacts(mut impacts: EventReader<ImpactEvent>, names: Query<&Name>) {
+    for impact in impacts.read() {
+        let name = |entity| {
+            names
+                .get(entity)
+                .map_or("unknown".into(), |n| n.to_string())
+        };
+        info!(
+            "{} hit {} at {:.3} deg latitude, {:.3} deg longitude with {:.1} m/s",
+            name(impact.craft),
+            name(impact.planet),
+            impact.latitude.to_degrees(),
+            impact.longitude.to_degrees(),
+            impact.speed
+        );
+    }
+}
+
+/// Lifts landed crafts off again when rewinding past their impact, with the velocity they hit the surface with
+#[allow(clippy::type_complexity)]
+pub fn lift_off(
+    mut commands: Commands,
+    mut crafts: Query<
+        (
+            Entity,
+            &Landed,
+            &mut FloatingOriginPosition,
+            &mut NBodyVelocity,
+        ),
+        Without<Planet>,
+    >,
+    planets: Query<&NBodyVelocity, With<Planet>>,
+    elapsed: Res<PhysicsElapsed>,
+    step: PhysicsStep,
+) {
+    let step = step.seconds();
+    if step > 0.0 {
+        return;
+    }
+
+    for (craft, landed, mut position, mut velocity) in crafts.iter_mut() {
+        // Negative time from the impact back to the end of this step
+        let since_impact = elapsed.seconds + step - landed.time;
+        if since_impact >= 0.0 {
+            continue;
+        }
+        if let Ok(p_velocity) = planets.get(landed.planet) {
+            position.0 += landed.velocity * since_impact;
+            velocity.0 = p_velocity.0 + landed.velocity;
+        }
+        commands.entity(craft).remove::<Landed>();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::{
+        app::App,
+        ecs::{event::Events, system::RunSystemOnce},
+    };
+
+    use crate::objects::components::FocusType;
+
+    use crate::physics::{
+        bundles::{NBodyActiveBundle, NBodyPassiveBundle},
+        resources::{PhysicsDirection, PhysicsTimestep},
+        step_physics, PhysicPlugin,
+    };
+
+    use super::*;
+
+    /// Simulation of the earth with the given step size
+    fn earth(timestep: f64) -> App {
+        let mut app = App::new();
+        app.add_plugins(PhysicPlugin)
+            .insert_resource(PhysicsTimestep(timestep));
+        app.world.spawn((
+            Planet {
+                axial_tilt: 0.4,
+                spin_velocity: 7.29e-5,
+                spin_position: 0.0,
+                name: "Earth".into(),
+                radius: 6.371e6,
+            },
+            NBodyActiveBundle::new(&DVec3::ZERO, 5.972e24),
+            FloatingOriginPosition(DVec3::ZERO),
+        ));
+        app
+    }
+
+    /// Craft crossing the whole earth within a single step, without any position inside the planet
+    fn tunnel(policy: ImpactPolicy) -> (App, Entity) {
+        let mut app = earth(1.0);
+        app.insert_resource(policy);
+        let craft = app
+            .world
+            .spawn((
+                Craft,
+                NBodyPassiveBundle::new(&DVec3::new(1.4e7, 0.0, 0.0)),
+                FloatingOriginPosition(DVec3::new(-7e6, 1e6, 0.0)),
+            ))
+            .id();
+        step_physics(&mut app.world);
+        (app, craft)
+    }
+
+    #[test]
+    fn fast_craft_lands() {
+        let (mut app, craft) = tunnel(ImpactPolicy::Land);
+        assert!(app.world.get::<Landed>(craft).is_some());
+
+        // Stopped on the side facing the approach
+        let position = app.world.get::<FloatingOriginPosition>(craft).expect("").0;
+        assert!((position.length() - 6.371e6).abs() < 1.0);
+        assert!(position.x < 0.0);
+        let mut impacts = app.world.resource_mut::<Events<ImpactEvent>>();
+        assert_eq!(impacts.drain().count(), 1);
+    }
+
+    #[test]
+    fn fast_craft_destroyed() {
+        let (app, craft) = tunnel(ImpactPolicy::Destroy);
+        assert!(app.world.get_entity(craft).is_none());
+    }
+
+    /// Momentum and kinetic energy of all planets
+    fn planet_totals(app: &mut App) -> (f64, DVec3, f64) {
+        let mut planets = app.world.query::<(&NBodyVelocity, &MassG)>();
+        planets.iter(&app.world).fold(
+            (0.0, DVec3::ZERO, 0.0),
+            |(mass, momentum, energy), (v, m)| {
+                (
+                    mass + m.0,
+                    momentum + v.0 * m.0,
+                    energy + 0.5 * m.0 * v.0.length_squared(),
+                )
+            },
+        )
+    }
+
+    /// Earth and moon touching with the given relative position, collided once by the policy
+    fn collide(
+        policy: BodyCollisionPolicy,
+        moon_position: DVec3,
+        moon_velocity: DVec3,
+    ) -> (App, (f64, DVec3, f64)) {
+        let mut app = App::new();
+        app.add_plugins(PhysicPlugin)
+            .insert_resource(PhysicsTimestep(1.0))
+            .insert_resource(policy);
+        for (name, radius, mass, position, velocity) in [
+            (
+                "Earth",
+                6.371e6,
+                5.972e24,
+                DVec3::ZERO,
+                DVec3::new(0.0, 30e3, 0.0),
+            ),
+            ("Moon", 1.737e6, 7.342e22, moon_position, moon_velocity),
+        ] {
+            app.world.spawn((
+                Planet {
+                    axial_tilt: 0.0,
+                    spin_velocity: 0.0,
+                    spin_position: 0.0,
+                    name: name.into(),
+                    radius,
+                },
+                NBodyActiveBundle::new(&velocity, mass),
+                FloatingOriginPosition(position),
+                Focusable {
+                    focus_min_distance: radius,
+                    focus_sphere_radius: radius,
+                    focus_type: FocusType::Scale,
+                },
+            ));
+        }
+        let before = planet_totals(&mut app);
+        app.world.run_system_once(collide_bodies);
+        (app, before)
+    }
+
+    #[test]
+    fn merge_conserves_mass_and_momentum() {
+        let (mut app, (mass, momentum, _)) = collide(
+            BodyCollisionPolicy::Merge,
+            DVec3::new(7e6, 3e6, 0.0),
+            DVec3::new(-2e3, 31e3, 1e3),
+        );
+        let mut planets = app.world.query::<&Planet>();
+        let names: Vec<&str> = planets.iter(&app.world).map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec!["Earth"]);
+
+        let (merged_mass, merged_momentum, _) = planet_totals(&mut app);
+        assert!((merged_mass - mass).abs() <= mass * 1e-15);
+        assert!((merged_momentum - momentum).length() <= momentum.length() * 1e-15);
+    }
+
+    #[test]
+    fn bounce_conserves_energy() {
+        for (position, velocity) in [
+            // Oblique approach, already overlapping
+            (DVec3::new(7e6, 3e6, 0.0), DVec3::new(-2e3, 31e3, 1e3)),
+            // Coinciding centers, separated along the relative motion
+            (DVec3::ZERO, DVec3::new(-2e3, 31e3, 1e3)),
+            // Coinciding centers at rest relative to each other
+            (DVec3::ZERO, DVec3::new(0.0, 30e3, 0.0)),
+        ] {
+            let (mut app, (_, momentum, energy)) =
+                collide(BodyCollisionPolicy::Bounce, position, velocity);
+            let (_, bounced_momentum, bounced_energy) = planet_totals(&mut app);
+            assert!((bounced_momentum - momentum).length() <= momentum.length() * 1e-12);
+            assert!((bounced_energy - energy).abs() <= energy * 1e-12);
+
+            // Separated to touching distance
+            let mut planets = app.world.query::<&FloatingOriginPosition>();
+            let positions: Vec<DVec3> = planets.iter(&app.world).map(|p| p.0).collect();
+            let distance = positions[0].distance(positions[1]);
+            assert!((distance - (6.371e6 + 1.737e6)).abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn rewinding_lifts_off() {
+        let mut app = earth(0.1);
+        // Falls from rest and hits the surface after about 165 s
+        let start = DVec3::new(0.0, 6.5e6, 0.0);
+        let craft = app
+            .world
+            .spawn((
+                Craft,
+                NBodyPassiveBundle::new(&DVec3::ZERO),
+                FloatingOriginPosition(start),
+            ))
+            .id();
+
+        for _ in 0..3000 {
+            step_physics(&mut app.world);
+        }
+        assert!(app.world.get::<Landed>(craft).is_some());
+
+        // Rewinding to the start retraces the fall, up to the error of the impact within a step
+        app.insert_resource(PhysicsDirection::Backward);
+        while app.world.resource::<PhysicsElapsed>().steps > 0 {
+            step_physics(&mut app.world);
+        }
+        assert!(app.world.get::<Landed>(craft).is_none());
+        let position = app.world.get::<FloatingOriginPosition>(craft).expect("").0;
+        let velocity = app.world.get::<NBodyVelocity>(craft).expect("").0;
+        assert!((position - start).length() < 100.0);
+        assert!(velocity.length() < 1.0);
+    }
+}
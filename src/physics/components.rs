@@ -1,4 +1,9 @@
-use bevy::{ecs::component::Component, math::DVec3};
+use bevy::{
+    ecs::{component::Component, entity::Entity},
+    math::DVec3,
+};
+use physical_constants::STANDARD_ACCELERATION_OF_GRAVITY;
+use serde::{Deserialize, Serialize};
 
 /// Marks entities as being affected by the n-body calculations.
 #[derive(Component)]
@@ -17,3 +22,236 @@ pub struct NBodyAcceleration(pub DVec3);
 /// Used as a gravity source during the n-body calculations.
 #[derive(Component)]
 pub struct MassG(pub f64);
+
+/// Zonal harmonic coefficients of an oblate planet, perturbing the gravity acting on crafts.
+/// The spin axis is the unit vector the harmonics are symmetric around.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct ZonalHarmonics {
+    pub j2: f64,
+    pub j3: f64,
+    pub j4: f64,
+    /// Radius the coefficients are normalized to in m
+    pub reference_radius: f64,
+    pub spin_axis: DVec3,
+}
+
+/// Atmosphere of a planet, the density follows the table if given and an exponential profile otherwise
+#[derive(Component, Serialize, Deserialize, Clone, Debug)]
+pub struct Atmosphere {
+    /// Density at the surface in kg/m^3
+    pub surface_density: f64,
+    /// Altitude over which the density falls by a factor of e, in m
+    pub scale_height: f64,
+    /// Altitude in m above which the density is zero, 30 scale heights if omitted
+    #[serde(default)]
+    pub ceiling: Option<f64>,
+    /// Pairs of altitude in m and density in kg/m^3, sorted by altitude
+    #[serde(default)]
+    pub table: Vec<[f64; 2]>,
+}
+
+/// Drag properties of a craft, crafts without a cross section are not affected by drag
+#[derive(Component)]
+pub struct Aerodynamics {
+    pub drag_coefficient: f64,
+    /// Area facing the flow in m^2
+    pub cross_section: f64,
+}
+
+/// Light emitting body, its radiation pushes crafts away
+#[derive(Component)]
+pub struct Star {
+    /// Radiated power in W
+    pub luminosity: f64,
+}
+
+/// Solar radiation pressure properties of a craft, crafts without an area are not affected
+#[derive(Component)]
+pub struct RadiationPressure {
+    /// Radiation pressure coefficient, 1 for a perfect absorber and 2 for a perfect mirror
+    pub reflectivity: f64,
+    /// Area facing the star in m^2
+    pub area: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EclipseState {
+    #[default]
+    Sunlit,
+    /// The star is partially covered
+    Penumbra,
+    /// The star is fully covered
+    Umbra,
+    /// The star is larger than the covering planet and visible around it
+    Antumbra,
+}
+
+/// Shadow of the brightest star on a craft, caused by the planet covering the largest part of it
+#[derive(Component)]
+pub struct Eclipse {
+    pub state: EclipseState,
+    /// Visible fraction of the star between 0 and 1
+    pub illumination: f64,
+    /// Star the eclipse refers to, Entity::PLACEHOLDER without stars
+    pub star: Entity,
+    /// Planet casting the shadow, Entity::PLACEHOLDER when sunlit
+    pub occulter: Entity,
+}
+
+impl Default for Eclipse {
+    fn default() -> Self {
+        Eclipse {
+            state: EclipseState::Sunlit,
+            illumination: 1.0,
+            star: Entity::PLACEHOLDER,
+            occulter: Entity::PLACEHOLDER,
+        }
+    }
+}
+
+/// The massive body whose sphere of influence currently contains the entity.
+/// Entity::PLACEHOLDER if the entity is not inside any sphere of influence, e.g. for the central body.
+#[derive(Component)]
+pub struct PrimaryBody(pub Entity);
+
+/// Craft resting on the surface of a planet, it follows the planet instead of being integrated
+#[derive(Component)]
+pub struct Landed {
+    pub planet: Entity,
+    /// Position on the surface in the body fixed frame of the planet
+    pub surface: DVec3,
+    /// Simulated seconds of the impact
+    pub time: f64,
+    /// Velocity relative to the planet at the impact, the craft lifts off with it when rewinding past the impact
+    pub velocity: DVec3,
+}
+
+/// Prograde, normal and radial unit vectors for a position and velocity relative to the primary
+pub fn orbit_frame(position: DVec3, velocity: DVec3) -> (DVec3, DVec3, DVec3) {
+    let prograde = velocity.normalize_or_zero();
+    let normal = position.cross(velocity).normalize_or_zero();
+    let radial = normal.cross(prograde);
+    (prograde, normal, radial)
+}
+
+/// Direction of the engine thrust, relative to the orbit around the PrimaryBody
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ThrustDirection {
+    #[default]
+    Prograde,
+    Retrograde,
+    Normal,
+    AntiNormal,
+    RadialOut,
+    RadialIn,
+}
+
+impl ThrustDirection {
+    pub const ALL: [ThrustDirection; 6] = [
+        ThrustDirection::Prograde,
+        ThrustDirection::Retrograde,
+        ThrustDirection::Normal,
+        ThrustDirection::AntiNormal,
+        ThrustDirection::RadialOut,
+        ThrustDirection::RadialIn,
+    ];
+
+    /// Unit vector of the direction for the given position and velocity relative to the primary
+    pub fn to_vector(self, position: DVec3, velocity: DVec3) -> DVec3 {
+        let (prograde, normal, radial) = orbit_frame(position, velocity);
+        match self {
+            ThrustDirection::Prograde => prograde,
+            ThrustDirection::Retrograde => -prograde,
+            ThrustDirection::Normal => normal,
+            ThrustDirection::AntiNormal => -normal,
+            ThrustDirection::RadialOut => radial,
+            ThrustDirection::RadialIn => -radial,
+        }
+    }
+}
+
+/// Engine and fuel of a craft. Masses are in kg, thrust in N and the specific impulse in s.
+#[derive(Component)]
+pub struct Propulsion {
+    pub dry_mass: f64,
+    pub fuel_mass: f64,
+    pub thrust: f64,
+    pub isp: f64,
+    /// Fraction of the full thrust between 0 and 1
+    pub throttle: f64,
+    pub direction: ThrustDirection,
+    /// Throttle and direction are currently controlled by a ScheduledBurn
+    pub scheduled: bool,
+    /// Inertial unit vector overriding the direction, set while executing a ManeuverNode
+    pub fixed_direction: Option<DVec3>,
+    /// Total velocity change delivered by the engine since the start
+    pub delivered_delta_v: f64,
+}
+
+impl Propulsion {
+    pub fn mass(&self) -> f64 {
+        self.dry_mass + self.fuel_mass
+    }
+
+    /// Velocity change the remaining fuel can provide according to the rocket equation
+    pub fn remaining_delta_v(&self) -> f64 {
+        if self.dry_mass <= 0.0 {
+            return 0.0;
+        }
+        self.isp * STANDARD_ACCELERATION_OF_GRAVITY * (self.mass() / self.dry_mass).ln()
+    }
+
+    /// Duration of a full throttle burn delivering delta_v, zero for crafts without an engine
+    pub fn burn_time(&self, delta_v: f64) -> f64 {
+        if self.thrust <= 0.0 || self.isp <= 0.0 {
+            return 0.0;
+        }
+        let exhaust_velocity = self.isp * STANDARD_ACCELERATION_OF_GRAVITY;
+        let end_mass = self.mass() * (-delta_v / exhaust_velocity).exp();
+        (self.mass() - end_mass) * exhaust_velocity / self.thrust
+    }
+}
+
+/// Burn executed automatically, start is the simulated time in seconds since the start of the simulation
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+pub struct ScheduledBurn {
+    pub start: f64,
+    pub duration: f64,
+    pub throttle: f64,
+    pub direction: ThrustDirection,
+}
+
+/// List of burns a craft executes on its own
+#[derive(Component, Default)]
+pub struct BurnSchedule(pub Vec<ScheduledBurn>);
+
+/// Planned velocity change of a craft at a future point of its orbit
+#[derive(Component)]
+pub struct ManeuverNode {
+    /// Simulated time of the node in seconds since the start of the simulation
+    pub time: f64,
+    /// Prograde, normal and radial velocity change in m/s
+    pub delta_v: DVec3,
+    /// Inertial velocity change, fixed once the burn has started
+    pub burn: Option<DVec3>,
+    /// Propulsion::delivered_delta_v when the burn started
+    pub burn_start: f64,
+}
+
+impl ManeuverNode {
+    pub fn new(time: f64) -> Self {
+        ManeuverNode {
+            time,
+            delta_v: DVec3::ZERO,
+            burn: None,
+            burn_start: 0.0,
+        }
+    }
+
+    /// Inertial velocity change for the given position and velocity relative to the primary
+    pub fn inertial_delta_v(&self, position: DVec3, velocity: DVec3) -> DVec3 {
+        let (prograde, normal, radial) = orbit_frame(position, velocity);
+        prograde * self.delta_v.x + normal * self.delta_v.y + radial * self.delta_v.z
+    }
+}
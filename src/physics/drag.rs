@@ -0,0 +1,87 @@
+use bevy::ecs::{query::Without, system::Query};
+
+use crate::{
+    floatingorigin::components::FloatingOriginPosition, objects::planet::components::Planet,
+    physics::components::*,
+};
+
+impl Atmosphere {
+    /// Density in kg/m^3 at the given altitude above the surface
+    pub fn density(&self, altitude: f64) -> f64 {
+        if self.table.is_empty() {
+            let ceiling = self.ceiling.unwrap_or(30.0 * self.scale_height);
+            if altitude > ceiling {
+                return 0.0;
+            }
+            return self.surface_density * (-altitude.max(0.0) / self.scale_height).exp();
+        }
+
+        let last = self.table[self.table.len() - 1];
+        if altitude > self.ceiling.unwrap_or(last[0]) {
+            return 0.0;
+        }
+
+        // Interpolate exponentially between the neighbouring table entries
+        let upper = self
+            .table
+            .iter()
+            .position(|[a, _]| *a >= altitude)
+            .unwrap_or(self.table.len() - 1)
+            .max(1);
+        let [a0, d0] = self.table[upper - 1];
+        let [a1, d1] = self.table[upper];
+        if a1 <= a0 || d0 <= 0.0 || d1 <= 0.0 {
+            return d0;
+        }
+        let fraction = ((altitude - a0) / (a1 - a0)).max(0.0);
+        d0 * (d1 / d0).powf(fraction)
+    }
+}
+
+///
+/// Adds the atmospheric drag of all planets with an Atmosphere to crafts with a cross section.
+/// The drag uses the velocity relative to the atmosphere, which rotates together with the planet.
+///
+#[allow(clippy::type_complexity)]
+pub fn apply_drag(
+    mut crafts: Query<
+        (
+            &Aerodynamics,
+            &Propulsion,
+            &FloatingOriginPosition,
+            &NBodyVelocity,
+            &mut NBodyAcceleration,
+        ),
+        Without<Landed>,
+    >,
+    planets: Query<(
+        &Planet,
+        &Atmosphere,
+        &FloatingOriginPosition,
+        &NBodyVelocity,
+    )>,
+) {
+    for (aerodynamics, propulsion, position, velocity, mut acc) in crafts.iter_mut() {
+        if aerodynamics.cross_section <= 0.0 || propulsion.mass() <= 0.0 {
+            continue;
+        }
+
+        for (planet, atmosphere, p_position, p_velocity) in planets.iter() {
+            let rel_position = position.0 - p_position.0;
+            let density = atmosphere.density(rel_position.length() - planet.radius);
+            if density <= 0.0 {
+                continue;
+            }
+
+            let airspeed =
+                velocity.0 - p_velocity.0 - planet.angular_velocity().cross(rel_position);
+            acc.0 -= airspeed
+                * (0.5
+                    * density
+                    * aerodynamics.drag_coefficient
+                    * aerodynamics.cross_section
+                    * airspeed.length()
+                    / propulsion.mass());
+        }
+    }
+}
@@ -0,0 +1,451 @@
+use std::{fmt, str::FromStr};
+
+/// Julian date of the J2000 epoch, 2000-01-01 12:00:00 TT
+const J2000_JULIAN_DATE: f64 = 2451545.0;
+/// Days between 1970-01-01 and 2000-01-01
+const J2000_CIVIL_DAYS: i64 = 10957;
+const SEC_PER_DAY: f64 = 86400.0;
+/// Constant offset of Terrestrial Time to International Atomic Time
+const TT_MINUS_TAI: f64 = 32.184;
+
+/// Difference TAI - UTC in seconds, valid from the first day of the given year and month.
+/// Earlier dates use the offset of 1972.
+const LEAP_SECONDS: [(i64, u32, f64); 28] = [
+    (1972, 1, 10.0),
+    (1972, 7, 11.0),
+    (1973, 1, 12.0),
+    (1974, 1, 13.0),
+    (1975, 1, 14.0),
+    (1976, 1, 15.0),
+    (1977, 1, 16.0),
+    (1978, 1, 17.0),
+    (1979, 1, 18.0),
+    (1980, 1, 19.0),
+    (1981, 7, 20.0),
+    (1982, 7, 21.0),
+    (1983, 7, 22.0),
+    (1985, 7, 23.0),
+    (1988, 1, 24.0),
+    (1990, 1, 25.0),
+    (1991, 1, 26.0),
+    (1992, 7, 27.0),
+    (1993, 7, 28.0),
+    (1994, 7, 29.0),
+    (1996, 1, 30.0),
+    (1997, 7, 31.0),
+    (1999, 1, 32.0),
+    (2006, 1, 33.0),
+    (2009, 1, 34.0),
+    (2012, 7, 35.0),
+    (2015, 7, 36.0),
+    (2017, 1, 37.0),
+];
+
+/// Time scale an epoch is read or written in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TimeScale {
+    /// Coordinated Universal Time, follows the rotation of the earth by inserting leap seconds
+    #[default]
+    Utc,
+    /// International Atomic Time
+    Tai,
+    /// Terrestrial Time
+    Tt,
+    /// Barycentric Dynamical Time, the time argument of the physics
+    Tdb,
+}
+impl FromStr for TimeScale {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "utc" | "z" => Ok(TimeScale::Utc),
+            "tai" => Ok(TimeScale::Tai),
+            "tt" => Ok(TimeScale::Tt),
+            "tdb" => Ok(TimeScale::Tdb),
+            _ => Err(format!("Unknown time scale {}", s)),
+        }
+    }
+}
+impl fmt::Display for TimeScale {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            TimeScale::Utc => "UTC",
+            TimeScale::Tai => "TAI",
+            TimeScale::Tt => "TT",
+            TimeScale::Tdb => "TDB",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Gregorian calendar date and time of day
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CalendarDate {
+    pub year: i64,
+    pub month: u32,
+    pub day: u32,
+    pub hour: u32,
+    pub minute: u32,
+    pub second: f64,
+}
+impl fmt::Display for CalendarDate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:06.3}",
+            self.year, self.month, self.day, self.hour, self.minute, self.second
+        )
+    }
+}
+impl FromStr for CalendarDate {
+    type Err = String;
+
+    ///
+    /// Parses ISO 8601 dates like 2024-03-20, 2024-03-20T12:30 or 2024-03-20T12:30:15.5Z.
+    /// Years before 1 are given astronomically with a sign, like -0044-03-15 for 45 BC.
+    ///
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid date {}", s);
+        let trimmed = s.trim().trim_end_matches(['Z', 'z']);
+        let (date, time) = match trimmed.split_once(['T', 't', ' ']) {
+            Some((date, time)) => (date, time.trim()),
+            None => (trimmed, ""),
+        };
+        let (sign, date) = match date.strip_prefix('-') {
+            Some(date) => (-1, date),
+            None => (1, date.strip_prefix('+').unwrap_or(date)),
+        };
+
+        let mut date_parts = date.splitn(3, '-');
+        let mut next_date = || date_parts.next().ok_or_else(invalid);
+        let year = sign * next_date()?.parse::<u32>().map_err(|_| invalid())? as i64;
+        let month = next_date()?.parse::<u32>().map_err(|_| invalid())?;
+        let day = next_date()?.parse::<u32>().map_err(|_| invalid())?;
+
+        let mut time_parts = time.splitn(3, ':').filter(|p| !p.is_empty());
+        let hour = time_parts.next().map_or(Ok(0), |h| h.parse::<u32>());
+        let minute = time_parts.next().map_or(Ok(0), |m| m.parse::<u32>());
+        let second = time_parts.next().map_or(Ok(0.0), |s| s.parse::<f64>());
+        let (Ok(hour), Ok(minute), Ok(second)) = (hour, minute, second) else {
+            return Err(invalid());
+        };
+
+        if !(1..=12).contains(&month)
+            || !(1..=days_in_month(year, month)).contains(&day)
+            || hour > 23
+            || minute > 59
+            || !(0.0..61.0).contains(&second)
+        {
+            return Err(invalid());
+        }
+        Ok(CalendarDate {
+            year,
+            month,
+            day,
+            hour,
+            minute,
+            second,
+        })
+    }
+}
+
+/// Number of days of the month in the proleptic Gregorian calendar
+fn days_in_month(year: i64, month: u32) -> u32 {
+    let leap = year.rem_euclid(4) == 0 && (year.rem_euclid(100) != 0 || year.rem_euclid(400) == 0);
+    match month {
+        2 if leap => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Days since 1970-01-01 of a date in the proleptic Gregorian calendar
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month_index = (month as i64 + 9) % 12;
+    let day_of_year = (153 * month_index + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+/// Date in the proleptic Gregorian calendar of the days since 1970-01-01
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days - era * 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    } as u32;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+/// Seconds past J2000 of the given date, counting every day with 86400 seconds
+fn calendar_seconds(date: &CalendarDate) -> f64 {
+    let days = days_from_civil(date.year, date.month, date.day) - J2000_CIVIL_DAYS;
+    (days as f64 - 0.5) * SEC_PER_DAY
+        + date.hour as f64 * 3600.0
+        + date.minute as f64 * 60.0
+        + date.second
+}
+
+/// TAI - UTC at the given UTC seconds past J2000
+fn leap_seconds_at_utc(utc: f64) -> f64 {
+    LEAP_SECONDS
+        .iter()
+        .take_while(|(year, month, _)| calendar_seconds(&midnight(*year, *month)) <= utc)
+        .last()
+        .map_or(LEAP_SECONDS[0].2, |(_, _, offset)| *offset)
+}
+
+/// TAI - UTC at the given TAI seconds past J2000.
+/// An inserted leap second still uses the previous offset.
+fn leap_seconds_at_tai(tai: f64) -> f64 {
+    LEAP_SECONDS
+        .iter()
+        .take_while(|(year, month, offset)| {
+            calendar_seconds(&midnight(*year, *month)) + offset <= tai
+        })
+        .last()
+        .map_or(LEAP_SECONDS[0].2, |(_, _, offset)| *offset)
+}
+
+fn midnight(year: i64, month: u32) -> CalendarDate {
+    CalendarDate {
+        year,
+        month,
+        day: 1,
+        hour: 0,
+        minute: 0,
+        second: 0.0,
+    }
+}
+
+/// TDB - TT at the given seconds past J2000, dominated by the eccentricity of the earth's orbit
+fn tdb_minus_tt(seconds: f64) -> f64 {
+    let mean_anomaly = (357.53 + 0.98560028 * seconds / SEC_PER_DAY).to_radians();
+    0.001657 * mean_anomaly.sin() + 0.000014 * (2.0 * mean_anomaly).sin()
+}
+
+///
+/// Instant in time, stored as seconds of Barycentric Dynamical Time (TDB) past J2000.
+/// Can be created from and converted to UTC, TAI, TT and TDB calendar dates or Julian dates.
+///
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Default)]
+pub struct Epoch(pub f64);
+impl Epoch {
+    pub const J2000: Epoch = Epoch(0.0);
+
+    /// Epoch from seconds past J2000 counted in the given time scale
+    pub fn from_seconds(seconds: f64, scale: TimeScale) -> Epoch {
+        let tt = match scale {
+            TimeScale::Tdb => return Epoch(seconds),
+            TimeScale::Tt => seconds,
+            TimeScale::Tai => seconds + TT_MINUS_TAI,
+            TimeScale::Utc => seconds + leap_seconds_at_utc(seconds) + TT_MINUS_TAI,
+        };
+        Epoch(tt + tdb_minus_tt(tt))
+    }
+
+    /// Seconds past J2000 counted in the given time scale
+    pub fn seconds(&self, scale: TimeScale) -> f64 {
+        if scale == TimeScale::Tdb {
+            return self.0;
+        }
+        // The periodic term changes too slowly to need more than a single correction
+        let tt = self.0 - tdb_minus_tt(self.0 - tdb_minus_tt(self.0));
+        let tai = tt - TT_MINUS_TAI;
+        match scale {
+            TimeScale::Tt => tt,
+            TimeScale::Tai => tai,
+            _ => tai - leap_seconds_at_tai(tai),
+        }
+    }
+
+    pub fn from_julian_date(julian_date: f64, scale: TimeScale) -> Epoch {
+        Epoch::from_seconds((julian_date - J2000_JULIAN_DATE) * SEC_PER_DAY, scale)
+    }
+
+    pub fn julian_date(&self, scale: TimeScale) -> f64 {
+        J2000_JULIAN_DATE + self.seconds(scale) / SEC_PER_DAY
+    }
+
+    pub fn from_calendar(date: &CalendarDate, scale: TimeScale) -> Epoch {
+        Epoch::from_seconds(calendar_seconds(date), scale)
+    }
+
+    /// Calendar date in the given time scale, rounded to milliseconds
+    pub fn calendar(&self, scale: TimeScale) -> CalendarDate {
+        let millis = ((self.seconds(scale) + SEC_PER_DAY / 2.0) * 1000.0).round() as i64;
+        let days = millis.div_euclid(86_400_000);
+        let of_day = millis.rem_euclid(86_400_000);
+        let (year, month, day) = civil_from_days(days + J2000_CIVIL_DAYS);
+        CalendarDate {
+            year,
+            month,
+            day,
+            hour: (of_day / 3_600_000) as u32,
+            minute: (of_day / 60_000 % 60) as u32,
+            second: (of_day % 60_000) as f64 / 1000.0,
+        }
+    }
+
+    /// Epoch the given number of TDB seconds later
+    pub fn offset(&self, seconds: f64) -> Epoch {
+        Epoch(self.0 + seconds)
+    }
+
+    /// Calendar date followed by the time scale, can be parsed again
+    pub fn format(&self, scale: TimeScale) -> String {
+        format!("{} {}", self.calendar(scale), scale)
+    }
+}
+impl FromStr for Epoch {
+    type Err = String;
+
+    ///
+    /// Parses an ISO 8601 date or a Julian date, optionally prefixed with JD.
+    /// A trailing utc, tai, tt or tdb selects the time scale, UTC is used otherwise.
+    ///
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts: Vec<&str> = s.split_whitespace().collect();
+        let scale = match parts.last().map(|p| p.parse::<TimeScale>()) {
+            Some(Ok(scale)) if parts.len() > 1 => {
+                parts.pop();
+                scale
+            }
+            _ => TimeScale::Utc,
+        };
+        let value = parts.join(" ");
+
+        let number = value
+            .strip_prefix("JD")
+            .or_else(|| value.strip_prefix("jd"))
+            .unwrap_or(&value)
+            .trim();
+        if let Ok(julian_date) = number.parse::<f64>() {
+            return Ok(Epoch::from_julian_date(julian_date, scale));
+        }
+        let date = value.parse::<CalendarDate>()?;
+        Ok(Epoch::from_calendar(&date, scale))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i64, month: u32, day: u32, hour: u32, minute: u32, second: f64) -> CalendarDate {
+        CalendarDate {
+            year,
+            month,
+            day,
+            hour,
+            minute,
+            second,
+        }
+    }
+
+    #[test]
+    fn j2000() {
+        let noon = Epoch::from_calendar(&date(2000, 1, 1, 12, 0, 0.0), TimeScale::Tt);
+        assert_eq!(noon.julian_date(TimeScale::Tt), 2451545.0);
+        assert_eq!(noon.seconds(TimeScale::Tt), 0.0);
+        assert_eq!(Epoch::J2000.julian_date(TimeScale::Tdb), 2451545.0);
+        assert!((noon.seconds(TimeScale::Tdb) - Epoch::J2000.0).abs() < 2e-3);
+    }
+
+    #[test]
+    fn time_scale_offsets() {
+        let epoch = Epoch::from_calendar(&date(2024, 3, 20, 3, 6, 0.0), TimeScale::Utc);
+        let tai = epoch.seconds(TimeScale::Tai);
+        assert!((tai - epoch.seconds(TimeScale::Utc) - 37.0).abs() < 1e-6);
+        assert!((epoch.seconds(TimeScale::Tt) - tai - 32.184).abs() < 1e-6);
+
+        let before = Epoch::from_calendar(&date(2016, 12, 31, 0, 0, 0.0), TimeScale::Utc);
+        let offset = before.seconds(TimeScale::Tai) - before.seconds(TimeScale::Utc);
+        assert!((offset - 36.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn leap_second_round_trip() {
+        let last = date(2016, 12, 31, 23, 59, 59.0);
+        let first = date(2017, 1, 1, 0, 0, 0.0);
+        let last_epoch = Epoch::from_calendar(&last, TimeScale::Utc);
+        let first_epoch = Epoch::from_calendar(&first, TimeScale::Utc);
+
+        // The inserted leap second passes between both dates
+        let elapsed = first_epoch.seconds(TimeScale::Tai) - last_epoch.seconds(TimeScale::Tai);
+        assert!((elapsed - 2.0).abs() < 1e-6);
+        assert_eq!(last_epoch.calendar(TimeScale::Utc), last);
+        assert_eq!(first_epoch.calendar(TimeScale::Utc), first);
+    }
+
+    #[test]
+    fn julian_date_round_trip() {
+        for scale in [
+            TimeScale::Utc,
+            TimeScale::Tai,
+            TimeScale::Tt,
+            TimeScale::Tdb,
+        ] {
+            for julian_date in [2415020.5, 2451545.0, 2460389.629] {
+                let epoch = Epoch::from_julian_date(julian_date, scale);
+                assert!((epoch.julian_date(scale) - julian_date).abs() < 1e-9);
+            }
+        }
+    }
+
+    #[test]
+    fn parse() {
+        let j2000 = Epoch::from_julian_date(2451545.0, TimeScale::Tt);
+        assert_eq!("JD 2451545.0 tt".parse::<Epoch>(), Ok(j2000));
+        assert_eq!("2451545 TT".parse::<Epoch>(), Ok(j2000));
+        assert_eq!("2000-01-01T12:00:00 tt".parse::<Epoch>(), Ok(j2000));
+
+        let epoch = "2024-03-20T03:06:00Z".parse::<Epoch>().expect("");
+        assert_eq!(epoch.calendar(TimeScale::Utc), date(2024, 3, 20, 3, 6, 0.0));
+        assert_eq!(
+            epoch
+                .format(TimeScale::Tai)
+                .parse::<Epoch>()
+                .map(|e| e.calendar(TimeScale::Utc)),
+            Ok(epoch.calendar(TimeScale::Utc))
+        );
+
+        let ides = "-0044-03-15".parse::<CalendarDate>();
+        assert_eq!(ides, Ok(date(-44, 3, 15, 0, 0, 0.0)));
+        let epoch = Epoch::from_calendar(&ides.expect(""), TimeScale::Tt);
+        assert_eq!(epoch.calendar(TimeScale::Tt), date(-44, 3, 15, 0, 0, 0.0));
+    }
+
+    #[test]
+    fn invalid_dates() {
+        assert!("2024-02-29".parse::<CalendarDate>().is_ok());
+        assert!("2000-02-29".parse::<CalendarDate>().is_ok());
+        for invalid in [
+            "2023-02-29",
+            "1900-02-29",
+            "2023-02-31",
+            "2023-04-31",
+            "2023-13-01",
+            "2023-01-00",
+            "2023-01-01T24:00",
+            "2023--01-01",
+            "March",
+        ] {
+            assert!(invalid.parse::<CalendarDate>().is_err(), "{}", invalid);
+        }
+    }
+}
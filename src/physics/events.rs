@@ -0,0 +1,20 @@
+use bevy::ecs::{entity::Entity, event::Event};
+
+/// Sent when a craft hits the surface of a planet.
+/// Latitude and longitude are in radians in the body fixed frame of the planet, the speed in m/s.
+#[derive(Event)]
+pub struct ImpactEvent {
+    pub craft: Entity,
+    pub planet: Entity,
+    pub latitude: f64,
+    pub longitude: f64,
+    pub speed: f64,
+}
+
+/// Sent when a body crosses a sphere of influence boundary and its PrimaryBody changes
+#[derive(Event)]
+pub struct PrimaryChangedEvent {
+    pub body: Entity,
+    pub previous: Entity,
+    pub current: Entity,
+}
@@ -0,0 +1,124 @@
+use bevy::math::DVec3;
+
+use super::components::ZonalHarmonics;
+
+impl ZonalHarmonics {
+    ///
+    /// Perturbing acceleration of the J2, J3 and J4 terms at a position relative to the planet center,
+    /// mass is the MassG of the planet. The point mass term is not included.
+    ///
+    pub fn acceleration(&self, position: DVec3, mass: f64) -> DVec3 {
+        let r_sq = position.length_squared();
+        if r_sq == 0.0 {
+            return DVec3::ZERO;
+        }
+        let r = r_sq.sqrt();
+
+        // Height above the equatorial plane and the position within it
+        let z = position.dot(self.spin_axis);
+        let equatorial = position - self.spin_axis * z;
+        let zr_sq = z * z / r_sq;
+        let radius = self.reference_radius;
+
+        let j2 = -1.5 * self.j2 * mass * radius.powi(2) / r.powi(5);
+        let j2_equatorial = j2 * (1.0 - 5.0 * zr_sq);
+        let j2_axial = j2 * (3.0 - 5.0 * zr_sq) * z;
+
+        let j3 = -2.5 * self.j3 * mass * radius.powi(3) / r.powi(7);
+        let j3_equatorial = j3 * (3.0 * z - 7.0 * z * zr_sq);
+        let j3_axial = j3 * (6.0 * z * z - 7.0 * z * z * zr_sq - 0.6 * r_sq);
+
+        let j4 = 1.875 * self.j4 * mass * radius.powi(4) / r.powi(7);
+        let j4_equatorial = j4 * (1.0 - 14.0 * zr_sq + 21.0 * zr_sq * zr_sq);
+        let j4_axial = j4 * (5.0 - 70.0 / 3.0 * zr_sq + 21.0 * zr_sq * zr_sq) * z;
+
+        equatorial * (j2_equatorial + j3_equatorial + j4_equatorial)
+            + self.spin_axis * (j2_axial + j3_axial + j4_axial)
+    }
+}
+
+///
+/// Adds the zonal harmonic perturbations of all oblate planets to the accelerations of the bodies
+/// without mass, i.e. crafts. Planets are given by their index into positions and masses.
+///
+pub fn add_zonal_accelerations(
+    positions: &[DVec3],
+    masses: &[f64],
+    planets: &[(usize, ZonalHarmonics)],
+    accelerations: &mut [DVec3],
+) {
+    for (planet, harmonics) in planets {
+        for (body, acc) in accelerations.iter_mut().enumerate() {
+            if masses[body] == 0.0 {
+                *acc +=
+                    harmonics.acceleration(positions[body] - positions[*planet], masses[*planet]);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::{PI, TAU};
+
+    use bevy::math::DVec3;
+
+    use crate::{
+        orbits::components::OrbitalElements,
+        physics::{components::ZonalHarmonics, resources::Integrator},
+    };
+
+    /// Gravitational parameter of the Earth in m^3/s^2
+    const MU: f64 = 3.986004418e14;
+    const EARTH_J2: f64 = 1.08263e-3;
+    const EARTH_RADIUS: f64 = 6378137.0;
+
+    /// Measured and analytic drift of the ascending node in rad/s for a J2-only Earth
+    fn nodal_precession(inclination: f64) -> (f64, f64) {
+        let harmonics = ZonalHarmonics {
+            j2: EARTH_J2,
+            j3: 0.0,
+            j4: 0.0,
+            reference_radius: EARTH_RADIUS,
+            spin_axis: DVec3::Z,
+        };
+        let start = OrbitalElements {
+            semi_major_axis: 7.0e6,
+            eccentricity: 0.01,
+            inclination,
+            ascending_node: 1.0,
+            ..Default::default()
+        };
+        let (position, velocity) = start.to_state(MU);
+        let mut positions = [position];
+        let mut velocities = [velocity];
+
+        let (dt, steps) = (10.0, 8640 * 5);
+        for _ in 0..steps {
+            Integrator::RungeKutta4.step(&mut positions, &mut velocities, dt, |pos, acc| {
+                acc[0] =
+                    -MU * pos[0] / pos[0].length().powi(3) + harmonics.acceleration(pos[0], MU);
+            });
+        }
+        let end = OrbitalElements::from_state(positions[0], velocities[0], MU);
+        let drift = (end.ascending_node - start.ascending_node + PI).rem_euclid(TAU) - PI;
+
+        let n = (MU / start.semi_major_axis.powi(3)).sqrt();
+        let p = start.semi_major_axis * (1.0 - start.eccentricity.powi(2));
+        let analytic = -1.5 * n * EARTH_J2 * (EARTH_RADIUS / p).powi(2) * inclination.cos();
+        (drift / (dt * steps as f64), analytic)
+    }
+
+    #[test]
+    fn j2_nodal_precession() {
+        for inclination in [0.5, 1.2, 2.0] {
+            let (measured, analytic) = nodal_precession(inclination);
+            assert!(
+                (measured - analytic).abs() < 0.02 * analytic.abs(),
+                "measured {} analytic {}",
+                measured,
+                analytic
+            );
+        }
+    }
+}
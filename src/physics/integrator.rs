@@ -1,35 +1,437 @@
 use bevy::{
-    ecs::system::{Query, Res},
+    ecs::{
+        query::{With, Without},
+        system::{Query, Res, ResMut},
+    },
+    log::warn,
     math::DVec3,
-    time::Time,
 };
 
 use crate::{floatingorigin::components::FloatingOriginPosition, physics::components::*};
 
-use super::PhysicsStepScale;
+use super::{
+    harmonics::add_zonal_accelerations,
+    nbody::GravitySettings,
+    resources::{Integrator, IntegratorSubstep, IntegratorTolerance, PhysicsStep},
+};
+
+/// Substeps tried at most by the adaptive integrator during a single physics step, including rejected ones
+const MAX_SUBSTEP_ATTEMPTS: u32 = 100000;
+
+// Dormand-Prince 5(4) coefficients
+const DP_A: [[f64; 6]; 7] = [
+    [0.0, 0.0, 0.0, 0.0, 0.0, 0.0],
+    [1.0 / 5.0, 0.0, 0.0, 0.0, 0.0, 0.0],
+    [3.0 / 40.0, 9.0 / 40.0, 0.0, 0.0, 0.0, 0.0],
+    [44.0 / 45.0, -56.0 / 15.0, 32.0 / 9.0, 0.0, 0.0, 0.0],
+    [
+        19372.0 / 6561.0,
+        -25360.0 / 2187.0,
+        64448.0 / 6561.0,
+        -212.0 / 729.0,
+        0.0,
+        0.0,
+    ],
+    [
+        9017.0 / 3168.0,
+        -355.0 / 33.0,
+        46732.0 / 5247.0,
+        49.0 / 176.0,
+        -5103.0 / 18656.0,
+        0.0,
+    ],
+    [
+        35.0 / 384.0,
+        0.0,
+        500.0 / 1113.0,
+        125.0 / 192.0,
+        -2187.0 / 6784.0,
+        11.0 / 84.0,
+    ],
+];
+const DP_B5: [f64; 7] = [
+    35.0 / 384.0,
+    0.0,
+    500.0 / 1113.0,
+    125.0 / 192.0,
+    -2187.0 / 6784.0,
+    11.0 / 84.0,
+    0.0,
+];
+const DP_B4: [f64; 7] = [
+    5179.0 / 57600.0,
+    0.0,
+    7571.0 / 16695.0,
+    393.0 / 640.0,
+    -92097.0 / 339200.0,
+    187.0 / 2100.0,
+    1.0 / 40.0,
+];
+
+impl Integrator {
+    ///
+    /// Advances positions and velocities by dt and reports the substeps used, a negative dt integrates backwards.
+    /// Adaptive integrators subdivide dt until the estimated error is within the tolerance,
+    /// the size of the previous substep is used as initial guess.
+    ///
+    pub fn advance<F>(
+        &self,
+        positions: &mut [DVec3],
+        velocities: &mut [DVec3],
+        dt: f64,
+        tolerance: f64,
+        previous: IntegratorSubstep,
+        mut acceleration: F,
+    ) -> IntegratorSubstep
+    where
+        F: FnMut(&[DVec3], &mut [DVec3]),
+    {
+        if *self != Integrator::DormandPrince45 {
+            self.step(positions, velocities, dt, acceleration);
+            return IntegratorSubstep {
+                size: dt.abs(),
+                count: 1,
+            };
+        }
+
+        // Substeps are sized by their magnitude, the direction is applied when stepping
+        let direction = dt.signum();
+        let span = dt.abs();
+        let min_size = span * 1e-6;
+        let mut size = if previous.size > 0.0 {
+            previous.size.min(span)
+        } else {
+            span
+        };
+        let mut smallest = span;
+        let mut count = 0;
+        let mut remaining = span;
+        let mut attempts = 0;
+        while remaining > 0.0 {
+            attempts += 1;
+            if attempts > MAX_SUBSTEP_ATTEMPTS {
+                warn!(
+                    "Adaptive integration gave up, {:.3e} s of the step were skipped",
+                    remaining
+                );
+                break;
+            }
+            let h = size.min(remaining);
+            let (new_pos, new_vel, error) = dormand_prince_step(
+                positions,
+                velocities,
+                h * direction,
+                tolerance,
+                &mut acceleration,
+            );
+
+            // Standard step size controller with safety factor, a non-finite error rejects the step
+            let factor = if !error.is_finite() {
+                0.2
+            } else if error > 0.0 {
+                (0.9 * error.powf(-0.2)).clamp(0.2, 5.0)
+            } else {
+                5.0
+            };
+
+            if error <= 1.0 || (h <= min_size && error.is_finite()) {
+                positions.copy_from_slice(&new_pos);
+                velocities.copy_from_slice(&new_vel);
+                remaining -= h;
+                smallest = smallest.min(h);
+                count += 1;
+            } else if h <= min_size {
+                warn!("Adaptive integration failed with a non-finite error, the rest of the step is skipped");
+                break;
+            }
+            size = (h * factor).max(min_size);
+        }
+
+        IntegratorSubstep {
+            size: smallest,
+            count,
+        }
+    }
+
+    ///
+    /// Advances positions and velocities by dt.
+    /// The acceleration function receives positions and writes the resulting acceleration for every body.
+    ///
+    pub fn step<F>(
+        &self,
+        positions: &mut [DVec3],
+        velocities: &mut [DVec3],
+        dt: f64,
+        mut acceleration: F,
+    ) where
+        F: FnMut(&[DVec3], &mut [DVec3]),
+    {
+        let mut acc = vec![DVec3::ZERO; positions.len()];
+
+        match self {
+            Integrator::SemiImplicitEuler => {
+                acceleration(positions, &mut acc);
+                for i in 0..positions.len() {
+                    velocities[i] += acc[i] * dt;
+                    positions[i] += velocities[i] * dt;
+                }
+            }
+            Integrator::VelocityVerlet => {
+                // Kick-drift-kick leapfrog
+                acceleration(positions, &mut acc);
+                for i in 0..positions.len() {
+                    velocities[i] += acc[i] * 0.5 * dt;
+                    positions[i] += velocities[i] * dt;
+                }
+                acceleration(positions, &mut acc);
+                for i in 0..positions.len() {
+                    velocities[i] += acc[i] * 0.5 * dt;
+                }
+            }
+            Integrator::RungeKutta4 => {
+                let n = positions.len();
+                let mut tmp = vec![DVec3::ZERO; n];
+                let mut k_pos = [
+                    vec![DVec3::ZERO; n],
+                    vec![DVec3::ZERO; n],
+                    vec![DVec3::ZERO; n],
+                    vec![DVec3::ZERO; n],
+                ];
+                let mut k_vel = [
+                    vec![DVec3::ZERO; n],
+                    vec![DVec3::ZERO; n],
+                    vec![DVec3::ZERO; n],
+                    vec![DVec3::ZERO; n],
+                ];
+                let stage_scale = [0.0, 0.5, 0.5, 1.0];
+
+                for stage in 0..4 {
+                    let h = stage_scale[stage] * dt;
+                    for i in 0..n {
+                        let (dx, dv) = if stage == 0 {
+                            (DVec3::ZERO, DVec3::ZERO)
+                        } else {
+                            (k_pos[stage - 1][i] * h, k_vel[stage - 1][i] * h)
+                        };
+                        tmp[i] = positions[i] + dx;
+                        k_pos[stage][i] = velocities[i] + dv;
+                    }
+                    acceleration(&tmp, &mut k_vel[stage]);
+                }
+
+                for i in 0..n {
+                    positions[i] +=
+                        (k_pos[0][i] + 2.0 * k_pos[1][i] + 2.0 * k_pos[2][i] + k_pos[3][i]) * dt
+                            / 6.0;
+                    velocities[i] +=
+                        (k_vel[0][i] + 2.0 * k_vel[1][i] + 2.0 * k_vel[2][i] + k_vel[3][i]) * dt
+                            / 6.0;
+                }
+            }
+            Integrator::Yoshida4 => {
+                // Fourth order symplectic composition of leapfrog steps
+                let cbrt2 = 2.0_f64.cbrt();
+                let w1 = 1.0 / (2.0 - cbrt2);
+                let w0 = -cbrt2 / (2.0 - cbrt2);
+                let drift = [w1 / 2.0, (w0 + w1) / 2.0, (w0 + w1) / 2.0, w1 / 2.0];
+                let kick = [w1, w0, w1];
+
+                for stage in 0..4 {
+                    for i in 0..positions.len() {
+                        positions[i] += velocities[i] * drift[stage] * dt;
+                    }
+                    if stage < 3 {
+                        acceleration(positions, &mut acc);
+                        for i in 0..positions.len() {
+                            velocities[i] += acc[i] * kick[stage] * dt;
+                        }
+                    }
+                }
+            }
+            Integrator::DormandPrince45 => {
+                let (new_pos, new_vel, _) =
+                    dormand_prince_step(positions, velocities, dt, 1.0, &mut acceleration);
+                positions.copy_from_slice(&new_pos);
+                velocities.copy_from_slice(&new_vel);
+            }
+        }
+    }
+}
 
 ///
-/// Uses a symplectic integrator to apply the sum of accelerations to the velocity and position at the of a timestep timestep
+/// Single embedded Dormand-Prince step of size h.
+/// Returns the fifth order solution and the error estimate normalized by the tolerance, infinite if it diverged.
 ///
+fn dormand_prince_step<F>(
+    positions: &[DVec3],
+    velocities: &[DVec3],
+    h: f64,
+    tolerance: f64,
+    acceleration: &mut F,
+) -> (Vec<DVec3>, Vec<DVec3>, f64)
+where
+    F: FnMut(&[DVec3], &mut [DVec3]),
+{
+    let n = positions.len();
+    let mut k_pos = vec![vec![DVec3::ZERO; n]; 7];
+    let mut k_vel = vec![vec![DVec3::ZERO; n]; 7];
+    let mut stage_pos = vec![DVec3::ZERO; n];
+
+    for stage in 0..7 {
+        for i in 0..n {
+            let mut dx = DVec3::ZERO;
+            let mut dv = DVec3::ZERO;
+            for prev in 0..stage {
+                dx += k_pos[prev][i] * DP_A[stage][prev];
+                dv += k_vel[prev][i] * DP_A[stage][prev];
+            }
+            stage_pos[i] = positions[i] + dx * h;
+            k_pos[stage][i] = velocities[i] + dv * h;
+        }
+        acceleration(&stage_pos, &mut k_vel[stage]);
+    }
+
+    let mut new_pos = positions.to_vec();
+    let mut new_vel = velocities.to_vec();
+    let mut error: f64 = 0.0;
+    for i in 0..n {
+        let mut err_pos = DVec3::ZERO;
+        let mut err_vel = DVec3::ZERO;
+        for stage in 0..7 {
+            new_pos[i] += k_pos[stage][i] * DP_B5[stage] * h;
+            new_vel[i] += k_vel[stage][i] * DP_B5[stage] * h;
+            err_pos += k_pos[stage][i] * (DP_B5[stage] - DP_B4[stage]) * h;
+            err_vel += k_vel[stage][i] * (DP_B5[stage] - DP_B4[stage]) * h;
+        }
+
+        let scale_pos = tolerance * (1.0 + positions[i].length().max(new_pos[i].length()));
+        let scale_vel = tolerance * (1.0 + velocities[i].length().max(new_vel[i].length()));
+        error = error
+            .max(err_pos.length() / scale_pos)
+            .max(err_vel.length() / scale_vel);
+
+        // max ignores NaN, so a diverged solution has to be flagged explicitly
+        if !new_pos[i].is_finite() || !new_vel[i].is_finite() {
+            error = f64::INFINITY;
+        }
+    }
+
+    (new_pos, new_vel, error)
+}
+
+///
+/// Advances all n-body entities by one fixed PhysicsTimestep using the selected Integrator, backwards while rewinding.
+/// Gravity, including the zonal harmonics of oblate planets, is reevaluated at every stage of the integrator,
+/// accelerations summed up in NBodyAcceleration are held constant during the step and reset afterwards.
+///
+#[allow(clippy::type_complexity)]
 pub fn integrate_time(
-    mut bodys_mut: Query<(
-        &mut FloatingOriginPosition,
-        &mut NBodyVelocity,
-        &mut NBodyAcceleration,
-    )>,
-    step_scale: Res<PhysicsStepScale>,
-    time: Res<Time>,
+    mut bodys_mut: Query<
+        (
+            &mut FloatingOriginPosition,
+            &mut NBodyVelocity,
+            &mut NBodyAcceleration,
+            Option<&MassG>,
+            Option<&ZonalHarmonics>,
+        ),
+        (With<NBodyEffector>, Without<Landed>),
+    >,
+    integrator: Res<Integrator>,
+    tolerance: Res<IntegratorTolerance>,
+    mut substep: ResMut<IntegratorSubstep>,
+    step: PhysicsStep,
+    gravity: GravitySettings,
 ) {
     //info!("integrate_time");
-    for (mut pos, mut vel, mut acc) in bodys_mut.iter_mut() {
-        // Scale timestep
-        let final_step = time.delta_seconds_f64() * step_scale.0 as f64;
+    // Scale timestep
+    let final_step = step.seconds();
+    let gravity = gravity.model();
+
+    let mut positions = Vec::new();
+    let mut velocities = Vec::new();
+    let mut masses = Vec::new();
+    let mut external = Vec::new();
+    let mut oblate = Vec::new();
+    for (index, (pos, vel, acc, mass, harmonics)) in bodys_mut.iter().enumerate() {
+        if let Some(harmonics) = harmonics {
+            oblate.push((index, *harmonics));
+        }
+        positions.push(pos.0);
+        velocities.push(vel.0);
+        masses.push(mass.map_or(0.0, |m| m.0));
+        external.push(acc.0);
+    }
+
+    *substep = integrator.advance(
+        &mut positions,
+        &mut velocities,
+        final_step,
+        tolerance.0,
+        *substep,
+        |pos, acc| {
+            gravity.accelerations(pos, &masses, acc);
+            add_zonal_accelerations(pos, &masses, &oblate, acc);
+            for (a, e) in acc.iter_mut().zip(external.iter()) {
+                *a += *e;
+            }
+        },
+    );
 
-        // Semi-implicit Euler method
-        vel.0 += acc.0 * final_step;
-        pos.0 += vel.0 * final_step;
+    for ((mut pos, mut vel, mut acc, _, _), (new_pos, new_vel)) in bodys_mut
+        .iter_mut()
+        .zip(positions.into_iter().zip(velocities))
+    {
+        pos.0 = new_pos;
+        vel.0 = new_vel;
 
         // Reset acceleration sum
         acc.0 = DVec3::ZERO;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use super::*;
+
+    /// Gravitational parameter of the earth in m^3/s^2
+    const MU: f64 = 3.986004418e14;
+
+    /// Position error after one period of an orbit with eccentricity 0.7, and the most substeps of a step
+    fn kepler_period(tolerance: f64) -> (f64, u32) {
+        let semi_major_axis = 2e7;
+        let periapsis = semi_major_axis * 0.3;
+        let start = DVec3::new(periapsis, 0.0, 0.0);
+        let speed = (MU * (2.0 / periapsis - 1.0 / semi_major_axis)).sqrt();
+        let period = 2.0 * PI * (semi_major_axis.powi(3) / MU).sqrt();
+
+        let mut positions = [start];
+        let mut velocities = [DVec3::new(0.0, speed, 0.0)];
+        let mut substep = IntegratorSubstep::default();
+        let mut most = 0;
+        for _ in 0..20 {
+            substep = Integrator::DormandPrince45.advance(
+                &mut positions,
+                &mut velocities,
+                period / 20.0,
+                tolerance,
+                substep,
+                |pos, acc| acc[0] = -pos[0] * MU / pos[0].length().powi(3),
+            );
+            most = most.max(substep.count);
+        }
+        ((positions[0] - start).length() / periapsis, most)
+    }
+
+    #[test]
+    fn dormand_prince_error_control() {
+        let (loose, loose_substeps) = kepler_period(1e-6);
+        let (tight, tight_substeps) = kepler_period(1e-12);
+        // Steps are subdivided around the periapsis, more so for the tight tolerance
+        assert!(loose_substeps > 1 && tight_substeps > loose_substeps);
+        assert!(loose < 1e-2);
+        assert!(tight < 1e-8);
+        assert!(tight < loose * 1e-3);
+    }
+}
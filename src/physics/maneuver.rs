@@ -0,0 +1,79 @@
+use bevy::ecs::{
+    entity::Entity,
+    query::Without,
+    system::{Commands, Query, Res},
+};
+
+use crate::{floatingorigin::components::FloatingOriginPosition, physics::components::*};
+
+use super::resources::{PhysicsElapsed, PhysicsStep};
+
+/// Remaining velocity change in m/s below which a maneuver burn is considered complete
+const BURN_COMPLETE: f64 = 1e-3;
+
+///
+/// Starts and controls the burns of ManeuverNodes.
+/// Burns are centered around the node time and keep the inertial direction fixed at their start.
+/// Crafts without an engine receive the velocity change instantly at the node.
+///
+pub fn execute_maneuver_nodes(
+    mut commands: Commands,
+    mut crafts: Query<(
+        Entity,
+        &mut ManeuverNode,
+        &mut Propulsion,
+        &mut NBodyVelocity,
+        &FloatingOriginPosition,
+        &PrimaryBody,
+    )>,
+    primaries: Query<(&FloatingOriginPosition, &NBodyVelocity), Without<ManeuverNode>>,
+    elapsed: Res<PhysicsElapsed>,
+    step: PhysicsStep,
+) {
+    // Nodes are only started and executed forwards in time
+    let step = step.seconds();
+    if step < 0.0 {
+        return;
+    }
+
+    for (entity, mut node, mut propulsion, mut velocity, position, primary) in crafts.iter_mut() {
+        if node.burn.is_none() {
+            let delta_v = node.delta_v.length();
+            let half_burn = propulsion.burn_time(delta_v) / 2.0;
+            if elapsed.seconds + step < node.time - half_burn {
+                continue;
+            }
+
+            let (rel_position, rel_velocity) = match primaries.get(primary.0) {
+                Ok((p_position, p_velocity)) => {
+                    (position.0 - p_position.0, velocity.0 - p_velocity.0)
+                }
+                Err(_) => (position.0, velocity.0),
+            };
+            let burn = node.inertial_delta_v(rel_position, rel_velocity);
+
+            if half_burn <= 0.0 || propulsion.fuel_mass <= 0.0 {
+                velocity.0 += burn;
+                commands.entity(entity).remove::<ManeuverNode>();
+                continue;
+            }
+
+            node.burn = Some(burn);
+            node.burn_start = propulsion.delivered_delta_v;
+        }
+
+        let burn = node.burn.expect("");
+        let remaining = burn.length() - (propulsion.delivered_delta_v - node.burn_start);
+        if remaining <= BURN_COMPLETE || propulsion.fuel_mass <= 0.0 {
+            propulsion.throttle = 0.0;
+            propulsion.fixed_direction = None;
+            commands.entity(entity).remove::<ManeuverNode>();
+            continue;
+        }
+
+        // Throttle down for the last step to hit the planned velocity change
+        let full_step = propulsion.thrust / propulsion.mass() * step;
+        propulsion.throttle = (remaining / full_step).min(1.0);
+        propulsion.fixed_direction = Some(burn.normalize());
+    }
+}
@@ -1,63 +1,246 @@
 use bevy::{
     app::{App, Plugin, Update},
     ecs::{
-        schedule::{IntoSystemConfigs, IntoSystemSetConfigs, Schedule, ScheduleLabel},
+        schedule::{IntoSystemConfigs, IntoSystemSetConfigs, ScheduleLabel},
         world::World,
     },
+    time::Time,
 };
 
 use crate::physics::{
+    collider::{collide_bodies, detect_impacts, follow_surface, lift_off, log_impacts},
+    drag::apply_drag,
+    events::{ImpactEvent, PrimaryChangedEvent},
     integrator::integrate_time,
-    nbody::nbody_accelerate,
-    resources::{PhysicsStepScale, PhysicsTimeScale},
+    maneuver::execute_maneuver_nodes,
+    radiation::{apply_radiation_pressure, update_eclipses},
+    resources::{
+        BodyCollisionPolicy, GravityOpeningAngle, GravityParallelism, GravitySoftening,
+        GravitySolver, ImpactPolicy, Integrator, IntegratorSubstep, IntegratorTolerance,
+        PhysicsAccumulator, PhysicsDirection, PhysicsElapsed, PhysicsStepScale, PhysicsTimeScale,
+        PhysicsTimestep, PrimaryRule, ShadowModel,
+    },
+    soi::{assign_primaries, log_primary_changes},
     systemsets::PhysicsSet,
+    thrust::{apply_thrust, execute_burn_schedule},
 };
 
 // Only expose components to world for queries
 pub mod bundles;
 pub mod components;
+pub mod epoch;
+pub mod events;
+pub mod nbody;
+pub mod propagation;
 pub mod resources;
 pub mod systemsets;
 
 // Keep the rest in module only
+mod barneshut;
+mod collider;
+mod drag;
+mod harmonics;
 mod integrator;
-mod nbody;
+mod maneuver;
+mod radiation;
+mod soi;
+mod thrust;
+
+/// Physics steps run at most during a single frame, the simulation falls behind the time scale beyond
+const MAX_STEPS_PER_FRAME: u32 = 10000;
 
-/// Schedule contining all physics related systems
+/// Schedule contining all physics related systems, run once per physics step.
+/// Other plugins may add systems to it, ordered by the PhysicsSet sets.
 #[derive(ScheduleLabel, Debug, Hash, PartialEq, Eq, Clone)]
-struct PhysicsSchedule;
+pub struct PhysicsSchedule;
 
 /// Plugin initializing the physics systems.
-/// PhysicsTimeScale and PhysicsStepScale are both initialized to 1.
+/// PhysicsTimeScale and PhysicsStepScale are both initialized to 1, PhysicsTimestep to 1/60 s.
+/// A negative PhysicsTimeScale runs the steps backwards until the start of the simulation is reached.
+/// PhysicsElapsed is the simulation clock, starting at J2000 unless another start epoch is inserted.
+/// The Integrator defaults to Velocity Verlet, overwrite the resource to select another scheme.
+/// IntegratorTolerance is only used by the adaptive Dormand-Prince integrator.
+/// Gravity is evaluated by the integrator, the Forces set is used for additional accelerations.
+/// Crafts hitting a planet land or are despawned according to the ImpactPolicy, landed crafts lift off
+/// again when rewinding past the impact. Colliding planets are handled according to the BodyCollisionPolicy.
 pub struct PhysicPlugin;
 impl Plugin for PhysicPlugin {
     fn build(&self, app: &mut App) {
-        // Create the Physics schedule containing all of our physics systems
-        let mut schedule = Schedule::new(PhysicsSchedule);
-        schedule
-            .add_systems((
-                nbody_accelerate.in_set(PhysicsSet::Forces),
+        // Fill the Physics schedule with all of our physics systems
+        app.add_systems(
+            PhysicsSchedule,
+            (
+                (execute_burn_schedule, execute_maneuver_nodes, apply_thrust)
+                    .chain()
+                    .in_set(PhysicsSet::Forces),
+                apply_drag.in_set(PhysicsSet::Forces),
+                (update_eclipses, apply_radiation_pressure)
+                    .chain()
+                    .in_set(PhysicsSet::Forces),
                 integrate_time.in_set(PhysicsSet::Integration),
-            ))
-            .configure_sets(PhysicsSet::Integration.after(PhysicsSet::Forces));
+                (collide_bodies, detect_impacts, follow_surface, lift_off)
+                    .chain()
+                    .in_set(PhysicsSet::Collision),
+                assign_primaries.in_set(PhysicsSet::Primary),
+            ),
+        )
+        .configure_sets(
+            PhysicsSchedule,
+            (
+                PhysicsSet::Integration.after(PhysicsSet::Forces),
+                PhysicsSet::Collision.after(PhysicsSet::Integration),
+                PhysicsSet::Primary.after(PhysicsSet::Collision),
+            ),
+        );
 
         // Run the Physics schedule
         fn run_physics_schedule(world: &mut World) {
-            let iterations = match world.get_resource::<PhysicsTimeScale>() {
-                None => 1 as u16,
-                Some(s) => s.0,
+            let time_scale = world.resource::<PhysicsTimeScale>().0;
+            let step_scale = world.resource::<PhysicsStepScale>().0 as f64;
+            let step = world.resource::<PhysicsTimestep>().0 * step_scale;
+            let frame_time = world.resource::<Time>().delta_seconds_f64();
+
+            let direction = if time_scale < 0 {
+                PhysicsDirection::Backward
+            } else {
+                PhysicsDirection::Forward
             };
+            world.insert_resource(direction);
 
-            // Repeat to advance simulation more than once per frame
-            for _ in 0..iterations {
-                world.run_schedule(PhysicsSchedule);
+            // Collect the simulated time of this frame, then consume it in fixed steps
+            world.resource_mut::<PhysicsAccumulator>().0 +=
+                frame_time * time_scale.unsigned_abs() as f64 * step_scale;
+            let mut steps = 0;
+            while world.resource::<PhysicsAccumulator>().0 >= step {
+                // Drop the simulated time which can not be caught up, slow steps would pile up otherwise
+                if steps == MAX_STEPS_PER_FRAME {
+                    world.resource_mut::<PhysicsAccumulator>().0 = 0.0;
+                    break;
+                }
+                // There is nothing to rewind before the start, pause instead
+                if direction == PhysicsDirection::Backward
+                    && world.resource::<PhysicsElapsed>().steps == 0
+                {
+                    world.resource_mut::<PhysicsTimeScale>().0 = 0;
+                    world.resource_mut::<PhysicsAccumulator>().0 = 0.0;
+                    break;
+                }
+                step_physics(world);
+                world.resource_mut::<PhysicsAccumulator>().0 -= step;
+                steps += 1;
             }
         }
 
         // Build plugin
         app.insert_resource(PhysicsTimeScale(1))
             .insert_resource(PhysicsStepScale(1))
-            .add_schedule(schedule)
-            .add_systems(Update, run_physics_schedule.in_set(PhysicsSet::All));
+            .insert_resource(PhysicsTimestep(1.0 / 60.0))
+            .insert_resource(PhysicsAccumulator::default())
+            .insert_resource(PhysicsElapsed::default())
+            .insert_resource(PhysicsDirection::default())
+            .insert_resource(Integrator::default())
+            .insert_resource(IntegratorTolerance(1e-10))
+            .insert_resource(IntegratorSubstep::default())
+            .insert_resource(PrimaryRule::default())
+            .insert_resource(ImpactPolicy::default())
+            .insert_resource(BodyCollisionPolicy::default())
+            .insert_resource(GravitySoftening(1e6))
+            .insert_resource(GravitySolver::default())
+            .insert_resource(GravityOpeningAngle(0.5))
+            .insert_resource(GravityParallelism::default())
+            .insert_resource(ShadowModel::default())
+            .add_event::<PrimaryChangedEvent>()
+            .add_event::<ImpactEvent>()
+            .add_systems(
+                Update,
+                (
+                    run_physics_schedule.in_set(PhysicsSet::All),
+                    (log_primary_changes, log_impacts).after(PhysicsSet::All),
+                ),
+            );
+    }
+}
+
+/// Advances the simulation by exactly one fixed physics step in the current PhysicsDirection.
+/// Independent of the frame time, so the same initial state always produces the same result.
+pub fn step_physics(world: &mut World) {
+    world.run_schedule(PhysicsSchedule);
+
+    let direction = *world.resource::<PhysicsDirection>();
+    let step =
+        world.resource::<PhysicsTimestep>().0 * world.resource::<PhysicsStepScale>().0 as f64;
+    let mut elapsed = world.resource_mut::<PhysicsElapsed>();
+    elapsed.seconds += step * direction.sign();
+    match direction {
+        PhysicsDirection::Forward => elapsed.steps += 1,
+        PhysicsDirection::Backward => elapsed.steps = elapsed.steps.saturating_sub(1),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::{app::App, ecs::entity::Entity, math::DVec3};
+
+    use crate::{
+        floatingorigin::components::FloatingOriginPosition, headless::benchmark::asteroid_belt,
+    };
+
+    use super::{
+        bundles::NBodyPassiveBundle,
+        components::{MassG, NBodyVelocity},
+        resources::{Integrator, PhysicsTimestep},
+        step_physics, PhysicPlugin,
+    };
+
+    /// Asteroid belt on circular orbits, large enough for the gravity to be computed in parallel
+    fn propagate(integrator: Integrator, steps: u32) -> Vec<(DVec3, DVec3)> {
+        let (positions, masses) = asteroid_belt(300);
+        let mut app = App::new();
+        app.add_plugins(PhysicPlugin)
+            .insert_resource(integrator)
+            .insert_resource(PhysicsTimestep(86400.0));
+
+        let bodies: Vec<Entity> = positions
+            .iter()
+            .zip(masses.iter())
+            .enumerate()
+            .map(|(index, (position, mass))| {
+                let velocity = if index == 0 {
+                    DVec3::ZERO
+                } else {
+                    DVec3::Z.cross(*position).normalize() * (masses[0] / position.length()).sqrt()
+                };
+                app.world
+                    .spawn((
+                        NBodyPassiveBundle::new(&velocity),
+                        MassG(*mass),
+                        FloatingOriginPosition(*position),
+                    ))
+                    .id()
+            })
+            .collect();
+
+        for _ in 0..steps {
+            step_physics(&mut app.world);
+        }
+        bodies
+            .iter()
+            .map(|body| {
+                let body = app.world.entity(*body);
+                (
+                    body.get::<FloatingOriginPosition>().expect("").0,
+                    body.get::<NBodyVelocity>().expect("").0,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn runs_are_bit_identical() {
+        for integrator in Integrator::ALL {
+            let first = propagate(integrator, 20);
+            assert_ne!(first, propagate(integrator, 0));
+            assert_eq!(first, propagate(integrator, 20), "{}", integrator.name());
+        }
     }
 }
@@ -1,66 +1,384 @@
 use bevy::{
-    ecs::{
-        entity::Entity,
-        query::{With, Without},
-        system::Query,
-    },
+    ecs::system::{Res, SystemParam},
     math::DVec3,
-    transform::components::Transform,
+    tasks::{ComputeTaskPool, TaskPool},
+};
+
+use super::{
+    barneshut::Octree,
+    resources::{
+        BodyCollisionPolicy, GravityOpeningAngle, GravityParallelism, GravitySoftening,
+        GravitySolver,
+    },
 };
 
-use crate::physics::components::*;
+/// Below this number of bodies the task overhead outweighs the parallel speedup
+const PARALLEL_THRESHOLD: usize = 256;
+
+/// Solver and parameters used to evaluate the gravity between all bodies
+#[derive(Clone, Copy, Debug, Default)]
+pub struct GravityModel {
+    pub solver: GravitySolver,
+    /// Opening angle of the Barnes-Hut solver
+    pub opening_angle: f64,
+    /// Plummer softening length, zero for exact point masses
+    pub softening: f64,
+    pub parallelism: GravityParallelism,
+}
+
+impl GravityModel {
+    ///
+    /// Computes the gravitational acceleration on every body caused by all bodies with a non-zero MassG.
+    /// Bodies without MassG are passed with a mass of zero, they are affected but do not attract.
+    /// Positions are absolute FloatingOriginPositions, so the result is independent of the camera.
+    /// A non-zero softening length applies Plummer softening, limiting the acceleration at small distances.
+    ///
+    pub fn accelerations(&self, positions: &[DVec3], masses: &[f64], accelerations: &mut [DVec3]) {
+        let parallelism = if positions.len() < PARALLEL_THRESHOLD {
+            GravityParallelism::Serial
+        } else {
+            self.parallelism
+        };
+
+        match self.solver {
+            GravitySolver::DirectSum => {
+                // Only bodies with mass act as sources, in the same order for every body
+                let sources: Vec<usize> = (0..masses.len()).filter(|i| masses[*i] != 0.0).collect();
+                let body_acc = |body: usize| -> DVec3 {
+                    sources.iter().filter(|source| **source != body).fold(
+                        DVec3::ZERO,
+                        |acc, source| {
+                            acc + point_mass_acc(
+                                positions[body],
+                                positions[*source],
+                                masses[*source],
+                                self.softening,
+                            )
+                        },
+                    )
+                };
+
+                match parallelism {
+                    GravityParallelism::Serial => accelerations
+                        .iter_mut()
+                        .enumerate()
+                        .for_each(|(body, acc)| *acc = body_acc(body)),
+                    GravityParallelism::Deterministic => parallel_bodies(accelerations, body_acc),
+                    GravityParallelism::Fast => pairwise_accelerations(
+                        positions,
+                        masses,
+                        &sources,
+                        self.softening,
+                        accelerations,
+                    ),
+                }
+            }
+            GravitySolver::BarnesHut => {
+                let tree = Octree::build(positions, masses);
+                let body_acc = |body: usize| {
+                    tree.acceleration(body, positions[body], self.opening_angle, self.softening)
+                };
+                match parallelism {
+                    GravityParallelism::Serial => accelerations
+                        .iter_mut()
+                        .enumerate()
+                        .for_each(|(body, acc)| *acc = body_acc(body)),
+                    _ => parallel_bodies(accelerations, body_acc),
+                }
+            }
+        }
+    }
+}
+
+/// Resources selecting the GravityModel, shared by all systems evaluating gravity
+#[derive(SystemParam)]
+pub struct GravitySettings<'w> {
+    solver: Res<'w, GravitySolver>,
+    opening_angle: Res<'w, GravityOpeningAngle>,
+    collision_policy: Res<'w, BodyCollisionPolicy>,
+    softening: Res<'w, GravitySoftening>,
+    parallelism: Res<'w, GravityParallelism>,
+}
+
+impl GravitySettings<'_> {
+    pub fn model(&self) -> GravityModel {
+        GravityModel {
+            solver: *self.solver,
+            opening_angle: self.opening_angle.0,
+            softening: self.collision_policy.softening(&self.softening),
+            parallelism: *self.parallelism,
+        }
+    }
+}
+
+/// Acceleration towards a point mass with the given MassG and Plummer softening length
+fn point_mass_acc(own_pos: DVec3, other_pos: DVec3, other_mass: f64, softening: f64) -> DVec3 {
+    let dist_vec = other_pos - own_pos;
+    let dist_sq = dist_vec.length_squared() + softening * softening;
+    (other_mass / (dist_sq * dist_sq.sqrt())) * dist_vec
+}
+
+///
+/// Evaluates body_acc for every body on the ComputeTaskPool, split into one chunk per thread.
+/// Every body is computed independently, so the result matches a serial evaluation bit for bit.
+///
+fn parallel_bodies<F>(accelerations: &mut [DVec3], body_acc: F)
+where
+    F: Fn(usize) -> DVec3 + Sync,
+{
+    let pool = ComputeTaskPool::get_or_init(TaskPool::default);
+    let chunk_size = accelerations
+        .len()
+        .div_ceil(pool.thread_num().max(1))
+        .max(1);
+    let body_acc = &body_acc;
+    pool.scope(|scope| {
+        for (chunk_index, chunk) in accelerations.chunks_mut(chunk_size).enumerate() {
+            scope.spawn(async move {
+                let first = chunk_index * chunk_size;
+                for (offset, acc) in chunk.iter_mut().enumerate() {
+                    *acc = body_acc(first + offset);
+                }
+            });
+        }
+    });
+}
 
 ///
-/// Computes the interactions on bodies with NBodyEffector caused by large bodies with MassG.
-/// Converts the gravitational force into a sum of accelerations.
+/// Direct sum evaluating every pair of massive bodies only once, applying the force to both.
+/// Pairs are distributed over the ComputeTaskPool with one partial sum per task, which are added
+/// up afterwards. Bodies without mass are evaluated independently against all sources.
+/// The summation order differs from the serial scheme, so results are not bit-identical.
 ///
-pub fn nbody_accelerate(
-    mut crafts_mut: Query<
-        (&mut NBodyAcceleration, &Transform),
-        (With<NBodyEffector>, Without<MassG>),
-    >,
-    mut planets_mut: Query<(Entity, &mut NBodyAcceleration, &Transform, &MassG), With<MassG>>,
+fn pairwise_accelerations(
+    positions: &[DVec3],
+    masses: &[f64],
+    sources: &[usize],
+    softening: f64,
+    accelerations: &mut [DVec3],
 ) {
-    //info!("nbody_accelerate");
-    fn gravity_acc(own_pos: DVec3, other_pos: DVec3, other_mass: f64) -> DVec3 {
-        let dist_vec = other_pos - own_pos;
-        let dist = dist_vec.length();
-        (other_mass / dist.powi(3)) * dist_vec
+    let pool = ComputeTaskPool::get_or_init(TaskPool::default);
+    let tasks = pool.thread_num().max(1);
+
+    // Rows are interleaved between the tasks, as earlier rows contain more pairs
+    let partial_sums = pool.scope(|scope| {
+        for task in 0..tasks {
+            scope.spawn(async move {
+                let mut partial = vec![DVec3::ZERO; positions.len()];
+                for row in (task..sources.len()).step_by(tasks) {
+                    let i = sources[row];
+                    for j in sources.iter().skip(row + 1) {
+                        let dist_vec = positions[*j] - positions[i];
+                        let dist_sq = dist_vec.length_squared() + softening * softening;
+                        let force = dist_vec / (dist_sq * dist_sq.sqrt());
+                        partial[i] += force * masses[*j];
+                        partial[*j] -= force * masses[i];
+                    }
+                }
+                partial
+            });
+        }
+    });
+
+    for (body, acc) in accelerations.iter_mut().enumerate() {
+        *acc = partial_sums
+            .iter()
+            .fold(DVec3::ZERO, |sum, p| sum + p[body]);
     }
 
-    // Calculate and apply acceleration on crafts
-    for (mut acc, craft_transform) in crafts_mut.iter_mut() {
-        //info!("Computing forces for craft");
-        for (_, _, planet_transform, planet_mass) in planets_mut.iter() {
-            acc.0 += gravity_acc(
-                craft_transform.translation.as_dvec3(),
-                planet_transform.translation.as_dvec3(),
-                planet_mass.0,
+    // Massless bodies only feel the sources
+    let massless: Vec<usize> = (0..masses.len()).filter(|i| masses[*i] == 0.0).collect();
+    let mut massless_acc = vec![DVec3::ZERO; massless.len()];
+    parallel_bodies(&mut massless_acc, |index| {
+        let body = massless[index];
+        sources.iter().fold(DVec3::ZERO, |acc, source| {
+            acc + point_mass_acc(
+                positions[body],
+                positions[*source],
+                masses[*source],
+                softening,
             )
-        }
-        //info!("Craft acceleration is {:?}", acc.0);
+        })
+    });
+    for (body, acc) in massless.iter().zip(massless_acc) {
+        accelerations[*body] = acc;
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::{
+        app::App, ecs::system::RunSystemOnce, math::DVec3, render::camera::Camera,
+        transform::components::Transform,
+    };
+
+    use crate::{
+        floatingorigin::{components::FloatingOriginPosition, floating_origin_transform},
+        physics::{
+            bundles::{NBodyActiveBundle, NBodyPassiveBundle},
+            step_physics, PhysicPlugin,
+        },
+    };
 
-    // Calculate acceleration on planets
-    let mut planets_acc_change: Vec<DVec3> = Vec::new();
-    for (id_dst, _, transform_dst, _) in planets_mut.iter() {
-        //info!("Computing forces for planet");
-        let mut planet_acc_change = DVec3::ZERO;
-        for (id_src, _, transform_src, mass) in planets_mut.iter() {
-            if id_dst == id_src {
-                continue;
+    use super::*;
+
+    const EARTH_MASS: f64 = 5.972e24;
+    const MOON_MASS: f64 = 7.342e22;
+
+    /// Earth, Moon and a craft in low orbit, stepped for ten simulated minutes.
+    /// The camera is moved to the given offset from the craft before every step.
+    fn propagate(camera_offset: Option<DVec3>) -> Vec<DVec3> {
+        let mut app = App::new();
+        app.add_plugins(PhysicPlugin);
+        let bodies = [
+            app.world
+                .spawn((
+                    NBodyActiveBundle::new(&DVec3::ZERO, EARTH_MASS),
+                    FloatingOriginPosition(DVec3::ZERO),
+                    Transform::default(),
+                ))
+                .id(),
+            app.world
+                .spawn((
+                    NBodyActiveBundle::new(&DVec3::new(0.0, 1022.0, 0.0), MOON_MASS),
+                    FloatingOriginPosition(DVec3::new(3.844e8, 0.0, 0.0)),
+                    Transform::default(),
+                ))
+                .id(),
+            app.world
+                .spawn((
+                    NBodyPassiveBundle::new(&DVec3::new(0.0, 0.0, 7668.0)),
+                    FloatingOriginPosition(DVec3::new(-6.778e6, 0.0, 0.0)),
+                    Transform::default(),
+                ))
+                .id(),
+        ];
+        let camera = camera_offset.map(|_| {
+            app.world
+                .spawn((Camera::default(), FloatingOriginPosition(DVec3::ZERO)))
+                .id()
+        });
+
+        for _ in 0..600 * 60 {
+            if let (Some(camera), Some(offset)) = (camera, camera_offset) {
+                let craft = app
+                    .world
+                    .get::<FloatingOriginPosition>(bodies[2])
+                    .expect("")
+                    .0;
+                app.world
+                    .get_mut::<FloatingOriginPosition>(camera)
+                    .expect("")
+                    .0 = craft + offset;
+                app.world.run_system_once(floating_origin_transform);
             }
-            planet_acc_change += gravity_acc(
-                transform_src.translation.as_dvec3(),
-                transform_dst.translation.as_dvec3(),
-                mass.0,
-            )
+            step_physics(&mut app.world);
+        }
+
+        bodies
+            .iter()
+            .map(|body| app.world.get::<FloatingOriginPosition>(*body).expect("").0)
+            .collect()
+    }
+
+    #[test]
+    fn trajectory_independent_of_camera() {
+        let reference = propagate(None);
+        assert_eq!(reference, propagate(Some(DVec3::ZERO)));
+        assert_eq!(reference, propagate(Some(DVec3::new(1e11, -3e10, 7e9))));
+    }
+
+    #[test]
+    fn bodies_attract_each_other() {
+        // The heavier body is listed second, so mixing up the order of the results changes the magnitudes
+        let positions = [
+            DVec3::ZERO,
+            DVec3::new(1e7, 0.0, 0.0),
+            DVec3::new(0.0, 1e7, 0.0),
+        ];
+        let masses = [1e12, 4e14, 0.0];
+        let mut accelerations = [DVec3::ZERO; 3];
+        GravityModel::default().accelerations(&positions, &masses, &mut accelerations);
+
+        let expected = [
+            DVec3::new(4e14 / 1e14, 0.0, 0.0),
+            DVec3::new(-1e12 / 1e14, 0.0, 0.0),
+            DVec3::new(1e7, -1e7, 0.0) * (4e14 / (2e14 * 2e14_f64.sqrt()))
+                + DVec3::new(0.0, -1e12 / 1e14, 0.0),
+        ];
+        for (acc, expected) in accelerations.iter().zip(expected) {
+            assert!((*acc - expected).length() <= 1e-12 * expected.length());
         }
-        planets_acc_change.push(planet_acc_change);
     }
 
-    // Apply acceleration on planets
-    for (_, mut acc, _, _) in planets_mut.iter_mut() {
-        acc.0 += planets_acc_change.pop().expect("");
+    /// Bodies scattered over a cube by a linear congruential generator, every fifth one massless
+    fn scattered_bodies(count: usize) -> (Vec<DVec3>, Vec<f64>) {
+        let mut state: u64 = 12345;
+        let mut next = || {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (state >> 11) as f64 / (1u64 << 53) as f64
+        };
+        (0..count)
+            .map(|i| {
+                let position = DVec3::new(next(), next(), next()) * 1e9;
+                let mass = if i % 5 == 0 { 0.0 } else { next() * 1e15 };
+                (position, mass)
+            })
+            .unzip()
+    }
+
+    fn model_accelerations(
+        solver: GravitySolver,
+        parallelism: GravityParallelism,
+        positions: &[DVec3],
+        masses: &[f64],
+    ) -> Vec<DVec3> {
+        let model = GravityModel {
+            solver,
+            opening_angle: 0.5,
+            softening: 0.0,
+            parallelism,
+        };
+        let mut accelerations = vec![DVec3::ZERO; positions.len()];
+        model.accelerations(positions, masses, &mut accelerations);
+        accelerations
+    }
+
+    #[test]
+    fn deterministic_matches_serial() {
+        let (positions, masses) = scattered_bodies(2 * PARALLEL_THRESHOLD + 3);
+        for solver in [GravitySolver::DirectSum, GravitySolver::BarnesHut] {
+            let serial =
+                model_accelerations(solver, GravityParallelism::Serial, &positions, &masses);
+            let deterministic = model_accelerations(
+                solver,
+                GravityParallelism::Deterministic,
+                &positions,
+                &masses,
+            );
+            assert_eq!(serial, deterministic);
+        }
+    }
+
+    #[test]
+    fn fast_matches_serial_closely() {
+        let (positions, masses) = scattered_bodies(2 * PARALLEL_THRESHOLD + 3);
+        let serial = model_accelerations(
+            GravitySolver::DirectSum,
+            GravityParallelism::Serial,
+            &positions,
+            &masses,
+        );
+        let fast = model_accelerations(
+            GravitySolver::DirectSum,
+            GravityParallelism::Fast,
+            &positions,
+            &masses,
+        );
+        for (serial, fast) in serial.iter().zip(fast) {
+            assert!((*serial - fast).length() <= 1e-10 * serial.length());
+        }
     }
 }
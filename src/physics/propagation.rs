@@ -0,0 +1,62 @@
+use bevy::{ecs::entity::Entity, math::DVec3};
+
+use super::{
+    components::ZonalHarmonics,
+    harmonics::add_zonal_accelerations,
+    nbody::GravityModel,
+    resources::{Integrator, IntegratorSubstep},
+};
+
+///
+/// Detached copy of the n-body state which can be propagated independently of the simulated world,
+/// e.g. in a background task. Only gravity is considered during the propagation.
+///
+#[derive(Clone, Default)]
+pub struct NBodySnapshot {
+    pub entities: Vec<Entity>,
+    pub positions: Vec<DVec3>,
+    pub velocities: Vec<DVec3>,
+    pub masses: Vec<f64>,
+    pub gravity: GravityModel,
+    /// Index and harmonics of all oblate bodies
+    pub oblate: Vec<(usize, ZonalHarmonics)>,
+    substep: IntegratorSubstep,
+}
+
+impl NBodySnapshot {
+    /// Adds a body, the mass is MassG or zero for bodies which do not attract others
+    pub fn push(
+        &mut self,
+        entity: Entity,
+        position: DVec3,
+        velocity: DVec3,
+        mass: f64,
+        harmonics: Option<ZonalHarmonics>,
+    ) {
+        if let Some(harmonics) = harmonics {
+            self.oblate.push((self.entities.len(), harmonics));
+        }
+        self.entities.push(entity);
+        self.positions.push(position);
+        self.velocities.push(velocity);
+        self.masses.push(mass);
+    }
+
+    /// Advances the snapshot by dt with the adaptive Dormand-Prince integrator
+    pub fn propagate(&mut self, dt: f64, tolerance: f64) {
+        let masses = &self.masses;
+        let gravity = self.gravity;
+        let oblate = &self.oblate;
+        self.substep = Integrator::DormandPrince45.advance(
+            &mut self.positions,
+            &mut self.velocities,
+            dt,
+            tolerance,
+            self.substep,
+            |pos, acc| {
+                gravity.accelerations(pos, masses, acc);
+                add_zonal_accelerations(pos, masses, oblate, acc);
+            },
+        );
+    }
+}
@@ -0,0 +1,150 @@
+use std::f64::consts::PI;
+
+use bevy::{
+    ecs::{
+        entity::Entity,
+        query::Without,
+        system::{Query, Res},
+    },
+    math::DVec3,
+};
+use physical_constants::SPEED_OF_LIGHT_IN_VACUUM;
+
+use crate::{
+    floatingorigin::components::FloatingOriginPosition, objects::planet::components::Planet,
+    physics::components::*,
+};
+
+use super::resources::ShadowModel;
+
+///
+/// Visible fraction of a star seen from position when a planet might cover it.
+/// Star and planet are given by their position and radius.
+///
+fn illumination(
+    model: ShadowModel,
+    position: DVec3,
+    star: (DVec3, f64),
+    planet: (DVec3, f64),
+) -> (f64, EclipseState) {
+    let to_star = star.0 - position;
+    let to_planet = planet.0 - position;
+
+    // Only planets between the position and the star cast a shadow
+    if to_planet.dot(to_star) <= 0.0 || to_planet.length() >= to_star.length() {
+        return (1.0, EclipseState::Sunlit);
+    }
+
+    match model {
+        ShadowModel::Cylindrical => {
+            let direction = to_star.normalize();
+            let distance = (to_planet - direction * to_planet.dot(direction)).length();
+            if distance < planet.1 {
+                (0.0, EclipseState::Umbra)
+            } else {
+                (1.0, EclipseState::Sunlit)
+            }
+        }
+        ShadowModel::Conical => {
+            // Apparent radii of both disks and their angular separation
+            let a = (star.1 / to_star.length()).min(1.0).asin();
+            let b = (planet.1 / to_planet.length()).min(1.0).asin();
+            let c = to_star.angle_between(to_planet);
+
+            if c >= a + b {
+                (1.0, EclipseState::Sunlit)
+            } else if c <= b - a {
+                (0.0, EclipseState::Umbra)
+            } else if c <= a - b {
+                (1.0 - b * b / (a * a), EclipseState::Antumbra)
+            } else {
+                // Overlapping area of both disks
+                let x = (c * c + a * a - b * b) / (2.0 * c);
+                let y = (a * a - x * x).max(0.0).sqrt();
+                let overlap = a * a * (x / a).clamp(-1.0, 1.0).acos()
+                    + b * b * ((c - x) / b).clamp(-1.0, 1.0).acos()
+                    - c * y;
+                (1.0 - overlap / (PI * a * a), EclipseState::Penumbra)
+            }
+        }
+    }
+}
+
+///
+/// Updates the Eclipse of every craft for the star with the largest flux at its position.
+/// The planet covering the largest part of the star determines the state.
+///
+pub fn update_eclipses(
+    mut crafts: Query<(&FloatingOriginPosition, &mut Eclipse)>,
+    stars: Query<(Entity, &Star, &Planet, &FloatingOriginPosition)>,
+    planets: Query<(Entity, &Planet, &FloatingOriginPosition), Without<Star>>,
+    model: Res<ShadowModel>,
+) {
+    for (position, mut eclipse) in crafts.iter_mut() {
+        let brightest = stars.iter().max_by(|(_, a, _, a_pos), (_, b, _, b_pos)| {
+            let a_flux = a.luminosity / a_pos.0.distance_squared(position.0);
+            let b_flux = b.luminosity / b_pos.0.distance_squared(position.0);
+            a_flux.total_cmp(&b_flux)
+        });
+        let Some((star, _, star_planet, star_position)) = brightest else {
+            *eclipse = Eclipse::default();
+            continue;
+        };
+
+        *eclipse = Eclipse {
+            star,
+            ..Default::default()
+        };
+        for (planet, planet_c, planet_position) in planets.iter() {
+            let (visible, state) = illumination(
+                *model,
+                position.0,
+                (star_position.0, star_planet.radius),
+                (planet_position.0, planet_c.radius),
+            );
+            if visible < eclipse.illumination {
+                eclipse.illumination = visible;
+                eclipse.state = state;
+                eclipse.occulter = planet;
+            }
+        }
+    }
+}
+
+///
+/// Adds the radiation pressure of all stars to crafts with a RadiationPressure area.
+/// The flux of the star referred to by the Eclipse is reduced by the visible fraction.
+///
+pub fn apply_radiation_pressure(
+    mut crafts: Query<
+        (
+            &RadiationPressure,
+            &Eclipse,
+            &Propulsion,
+            &FloatingOriginPosition,
+            &mut NBodyAcceleration,
+        ),
+        Without<Landed>,
+    >,
+    stars: Query<(Entity, &Star, &FloatingOriginPosition)>,
+) {
+    for (radiation, eclipse, propulsion, position, mut acc) in crafts.iter_mut() {
+        if radiation.area <= 0.0 || propulsion.mass() <= 0.0 {
+            continue;
+        }
+
+        for (star, star_c, star_position) in stars.iter() {
+            let away = position.0 - star_position.0;
+            let visible = if star == eclipse.star {
+                eclipse.illumination
+            } else {
+                1.0
+            };
+            let pressure =
+                star_c.luminosity / (4.0 * PI * SPEED_OF_LIGHT_IN_VACUUM * away.length_squared());
+            acc.0 += away.normalize()
+                * (visible * pressure * radiation.reflectivity * radiation.area
+                    / propulsion.mass());
+        }
+    }
+}
@@ -1,10 +1,290 @@
-use bevy::ecs::system::Resource;
+use std::str::FromStr;
 
-/// Determines how many times the physics schedules are run per frame
+use bevy::ecs::system::{Res, Resource, SystemParam};
+
+use super::epoch::Epoch;
+
+/// Determines how much faster than real time the simulation advances,
+/// more physics steps are run per frame when increased. Negative values run the simulation backwards.
 #[derive(Resource)]
-pub struct PhysicsTimeScale(pub u16);
+pub struct PhysicsTimeScale(pub i32);
+
+/// Direction of time the physics steps currently advance in, follows the sign of the PhysicsTimeScale
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PhysicsDirection {
+    #[default]
+    Forward,
+    Backward,
+}
+impl PhysicsDirection {
+    pub fn sign(&self) -> f64 {
+        match self {
+            PhysicsDirection::Forward => 1.0,
+            PhysicsDirection::Backward => -1.0,
+        }
+    }
+}
 
 /// Scales the timestep duration by integer multiple.
 /// Too large values might cause instabilities during the integration step.
 #[derive(Resource)]
 pub struct PhysicsStepScale(pub u16);
+
+/// Simulated duration of a single physics step in seconds, before scaling by PhysicsStepScale
+#[derive(Resource)]
+pub struct PhysicsTimestep(pub f64);
+
+/// Signed simulated duration of a physics step, shared by all systems depending on the step size
+#[derive(SystemParam)]
+pub struct PhysicsStep<'w> {
+    timestep: Res<'w, PhysicsTimestep>,
+    step_scale: Res<'w, PhysicsStepScale>,
+    direction: Res<'w, PhysicsDirection>,
+}
+impl PhysicsStep<'_> {
+    /// Duration of the step in seconds, negative while running backwards
+    pub fn seconds(&self) -> f64 {
+        self.timestep.0 * self.step_scale.0 as f64 * self.direction.sign()
+    }
+}
+
+/// Simulated time which has not yet been advanced by a full physics step
+#[derive(Resource, Default)]
+pub struct PhysicsAccumulator(pub f64);
+
+/// Simulated time and number of physics steps since the start of the simulation.
+/// The shared simulation clock, the current epoch is the start epoch advanced by the elapsed seconds.
+#[derive(Resource, Default)]
+pub struct PhysicsElapsed {
+    pub seconds: f64,
+    pub steps: u64,
+    pub start: Epoch,
+}
+impl PhysicsElapsed {
+    /// Clock starting at the given epoch
+    pub fn starting_at(start: Epoch) -> PhysicsElapsed {
+        PhysicsElapsed {
+            start,
+            ..Default::default()
+        }
+    }
+
+    /// Current epoch of the simulation
+    pub fn now(&self) -> Epoch {
+        self.start.offset(self.seconds)
+    }
+}
+
+/// Numerical scheme used to advance positions and velocities during a timestep
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Integrator {
+    SemiImplicitEuler,
+    #[default]
+    VelocityVerlet,
+    RungeKutta4,
+    Yoshida4,
+    DormandPrince45,
+}
+
+impl Integrator {
+    pub const ALL: [Integrator; 5] = [
+        Integrator::SemiImplicitEuler,
+        Integrator::VelocityVerlet,
+        Integrator::RungeKutta4,
+        Integrator::Yoshida4,
+        Integrator::DormandPrince45,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Integrator::SemiImplicitEuler => "Euler",
+            Integrator::VelocityVerlet => "Verlet",
+            Integrator::RungeKutta4 => "RK4",
+            Integrator::Yoshida4 => "Yoshida4",
+            Integrator::DormandPrince45 => "DP45",
+        }
+    }
+}
+
+impl FromStr for Integrator {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "euler" => Ok(Integrator::SemiImplicitEuler),
+            "verlet" => Ok(Integrator::VelocityVerlet),
+            "rk4" => Ok(Integrator::RungeKutta4),
+            "yoshida" | "yoshida4" => Ok(Integrator::Yoshida4),
+            "dp45" | "adaptive" => Ok(Integrator::DormandPrince45),
+            _ => Err(format!("Unknown integrator {}", s)),
+        }
+    }
+}
+
+/// Error tolerance of the adaptive integrator, relative to the magnitude of each position and velocity
+#[derive(Resource)]
+pub struct IntegratorTolerance(pub f64);
+
+/// Size and number of the substeps used by the integrator during the last physics step.
+/// Fixed step integrators always use a single substep of the full timestep.
+#[derive(Resource, Default, Clone, Copy)]
+pub struct IntegratorSubstep {
+    pub size: f64,
+    pub count: u32,
+}
+
+/// Rule used to choose the PrimaryBody of each body
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PrimaryRule {
+    /// Smallest Laplace sphere of influence containing the body
+    #[default]
+    Laplace,
+    /// Body exerting the strongest gravitational acceleration
+    StrongestGravity,
+}
+
+impl FromStr for PrimaryRule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "laplace" | "soi" => Ok(PrimaryRule::Laplace),
+            "gravity" | "strongest" => Ok(PrimaryRule::StrongestGravity),
+            _ => Err(format!("Unknown primary rule {}", s)),
+        }
+    }
+}
+
+/// Outcome of a craft hitting the surface of a planet
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ImpactPolicy {
+    /// The craft stops and stays on the surface
+    #[default]
+    Land,
+    /// The craft is removed from the simulation
+    Destroy,
+}
+
+impl FromStr for ImpactPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "land" | "landed" => Ok(ImpactPolicy::Land),
+            "destroy" | "despawn" => Ok(ImpactPolicy::Destroy),
+            _ => Err(format!("Unknown impact policy {}", s)),
+        }
+    }
+}
+
+/// Handling of two bodies with MassG touching each other
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BodyCollisionPolicy {
+    /// Combine both bodies into one, conserving mass and momentum
+    #[default]
+    Merge,
+    /// Elastic collision along the line connecting both centers
+    Bounce,
+    /// Bodies pass through each other, gravity is softened by GravitySoftening
+    Soften,
+}
+
+impl BodyCollisionPolicy {
+    /// Plummer softening length used by the gravity calculation, zero unless softening is selected
+    pub fn softening(&self, length: &GravitySoftening) -> f64 {
+        match self {
+            BodyCollisionPolicy::Soften => length.0,
+            _ => 0.0,
+        }
+    }
+}
+
+impl FromStr for BodyCollisionPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "merge" => Ok(BodyCollisionPolicy::Merge),
+            "bounce" => Ok(BodyCollisionPolicy::Bounce),
+            "soften" | "softening" | "plummer" => Ok(BodyCollisionPolicy::Soften),
+            _ => Err(format!("Unknown body collision policy {}", s)),
+        }
+    }
+}
+
+/// Plummer softening length in m, only used with BodyCollisionPolicy::Soften
+#[derive(Resource)]
+pub struct GravitySoftening(pub f64);
+
+/// Method computing the gravitational accelerations between all bodies
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GravitySolver {
+    /// Exact sum over all pairs of bodies, O(N^2)
+    #[default]
+    DirectSum,
+    /// Octree approximation of distant groups of bodies, O(N log N)
+    BarnesHut,
+}
+
+impl FromStr for GravitySolver {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "direct" | "directsum" => Ok(GravitySolver::DirectSum),
+            "barneshut" | "barnes-hut" | "bh" => Ok(GravitySolver::BarnesHut),
+            _ => Err(format!("Unknown gravity solver {}", s)),
+        }
+    }
+}
+
+/// Opening angle of the Barnes-Hut solver, smaller values are more accurate but slower
+#[derive(Resource)]
+pub struct GravityOpeningAngle(pub f64);
+
+/// Distribution of the gravity computation over the ComputeTaskPool
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GravityParallelism {
+    /// Single threaded
+    Serial,
+    /// Bodies are computed in parallel, results are bit-identical to the serial computation
+    #[default]
+    Deterministic,
+    /// Each pair of massive bodies is only evaluated once, results may differ in the last bits
+    Fast,
+}
+
+impl FromStr for GravityParallelism {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "serial" | "off" => Ok(GravityParallelism::Serial),
+            "deterministic" | "on" => Ok(GravityParallelism::Deterministic),
+            "fast" | "pairwise" => Ok(GravityParallelism::Fast),
+            _ => Err(format!("Unknown gravity parallelism {}", s)),
+        }
+    }
+}
+
+/// Geometry used to compute the shadows of planets
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ShadowModel {
+    /// Shadow of the planet diameter parallel to the star direction, without penumbra
+    Cylindrical,
+    /// Umbra and penumbra cones from the apparent disks of star and planet
+    #[default]
+    Conical,
+}
+
+impl FromStr for ShadowModel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "cylindrical" | "cylinder" => Ok(ShadowModel::Cylindrical),
+            "conical" | "cone" => Ok(ShadowModel::Conical),
+            _ => Err(format!("Unknown shadow model {}", s)),
+        }
+    }
+}
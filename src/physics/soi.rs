@@ -0,0 +1,232 @@
+use bevy::{
+    core::Name,
+    ecs::{
+        entity::Entity,
+        event::{EventReader, EventWriter},
+        system::{Query, Res},
+    },
+    log::info,
+    math::DVec3,
+};
+
+use crate::{floatingorigin::components::FloatingOriginPosition, physics::components::*};
+
+use super::{events::PrimaryChangedEvent, resources::PrimaryRule};
+
+/// Massive body taking part in the primary assignment
+struct Attractor {
+    entity: Entity,
+    position: DVec3,
+    mass: f64,
+    soi_radius: f64,
+}
+
+///
+/// Builds the attractor list including the Laplace sphere of influence radius r = a (m / M)^(2/5).
+/// The parent of an attractor is the more massive body with the smallest sphere of influence containing it,
+/// so a moon orbits its planet even where the star pulls stronger. Spheres are computed from the most
+/// massive body down, which has no parent and an infinite sphere of influence.
+///
+fn build_attractors(massive: &[(Entity, DVec3, f64)]) -> Vec<Attractor> {
+    let mut order: Vec<usize> = (0..massive.len()).collect();
+    order.sort_by(|a, b| massive[*b].2.total_cmp(&massive[*a].2));
+
+    let mut soi_radii = vec![f64::INFINITY; massive.len()];
+    for (rank, body) in order.iter().enumerate() {
+        let (_, position, mass) = massive[*body];
+        let parent = order[..rank]
+            .iter()
+            .filter(|other| {
+                let (_, other_position, other_mass) = massive[**other];
+                other_mass > mass && (other_position - position).length() < soi_radii[**other]
+            })
+            .min_by(|a, b| soi_radii[**a].total_cmp(&soi_radii[**b]));
+
+        if let Some(parent) = parent {
+            let (_, parent_position, parent_mass) = massive[*parent];
+            soi_radii[*body] =
+                (parent_position - position).length() * (mass / parent_mass).powf(0.4);
+        }
+    }
+
+    massive
+        .iter()
+        .zip(soi_radii)
+        .map(|((entity, position, mass), soi_radius)| Attractor {
+            entity: *entity,
+            position: *position,
+            mass: *mass,
+            soi_radius,
+        })
+        .collect()
+}
+
+/// Chooses the primary of a body, only bodies more massive than the body itself are considered
+fn choose_primary(
+    attractors: &[Attractor],
+    position: DVec3,
+    mass: f64,
+    rule: PrimaryRule,
+) -> Entity {
+    let candidates = attractors.iter().filter(|a| a.mass > mass);
+
+    let primary = match rule {
+        PrimaryRule::Laplace => candidates
+            .filter(|a| (a.position - position).length() < a.soi_radius)
+            .min_by(|a, b| a.soi_radius.total_cmp(&b.soi_radius)),
+        PrimaryRule::StrongestGravity => candidates.max_by(|a, b| {
+            let pull_a = a.mass / (a.position - position).length_squared();
+            let pull_b = b.mass / (b.position - position).length_squared();
+            pull_a.total_cmp(&pull_b)
+        }),
+    };
+
+    primary.map_or(Entity::PLACEHOLDER, |a| a.entity)
+}
+
+///
+/// Assigns every body with a PrimaryBody to the massive body it is currently orbiting.
+/// Sends a PrimaryChangedEvent whenever the primary changes.
+///
+pub fn assign_primaries(
+    massive_q: Query<(Entity, &FloatingOriginPosition, &MassG)>,
+    mut bodies_q: Query<(
+        Entity,
+        &FloatingOriginPosition,
+        Option<&MassG>,
+        &mut PrimaryBody,
+    )>,
+    rule: Res<PrimaryRule>,
+    mut primary_changed: EventWriter<PrimaryChangedEvent>,
+) {
+    let massive: Vec<(Entity, DVec3, f64)> = massive_q
+        .iter()
+        .map(|(entity, position, mass)| (entity, position.0, mass.0))
+        .collect();
+    let attractors = build_attractors(&massive);
+
+    for (entity, position, mass, mut primary) in bodies_q.iter_mut() {
+        let current = choose_primary(&attractors, position.0, mass.map_or(0.0, |m| m.0), *rule);
+
+        if current != primary.0 {
+            primary_changed.send(PrimaryChangedEvent {
+                body: entity,
+                previous: primary.0,
+                current,
+            });
+            primary.0 = current;
+        }
+    }
+}
+
+/// Logs all sphere of influence transitions
+pub fn log_primary_changes(
+    mut primary_changed: EventReader<PrimaryChangedEvent>,
+    names: Query<&Name>,
+) {
+    let name_of = |entity: Entity| {
+        names
+            .get(entity)
+            .map_or("none".to_string(), |name| name.to_string())
+    };
+
+    for event in primary_changed.read() {
+        info!(
+            "{} left the sphere of influence of {} and entered {}",
+            name_of(event.body),
+            name_of(event.previous),
+            name_of(event.current)
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::{
+        app::App,
+        ecs::{event::Events, system::RunSystemOnce},
+    };
+
+    use crate::physics::{bundles::NBodyActiveBundle, PhysicPlugin};
+
+    use super::*;
+
+    const AU: f64 = 1.495978707e11;
+    const MOON_DISTANCE: f64 = 3.844e8;
+
+    #[test]
+    fn laplace_spheres() {
+        let (sun, earth, moon) = (
+            Entity::from_raw(0),
+            Entity::from_raw(1),
+            Entity::from_raw(2),
+        );
+        let earth_position = DVec3::new(AU, 0.0, 0.0);
+        let moon_position = earth_position + DVec3::new(0.0, MOON_DISTANCE, 0.0);
+        let attractors = build_attractors(&[
+            (sun, DVec3::ZERO, 1.989e30),
+            (earth, earth_position, 5.972e24),
+            (moon, moon_position, 7.342e22),
+        ]);
+
+        // Earth about 9.2e8 m, moon about 6.6e7 m
+        assert!((attractors[1].soi_radius / 9.25e8 - 1.0).abs() < 0.01);
+        assert!((attractors[2].soi_radius / 6.62e7 - 1.0).abs() < 0.01);
+
+        let primary = |position| choose_primary(&attractors, position, 0.0, PrimaryRule::Laplace);
+        assert_eq!(primary(moon_position + DVec3::new(1e7, 0.0, 0.0)), moon);
+        assert_eq!(primary(earth_position - DVec3::new(0.0, 1e8, 0.0)), earth);
+        assert_eq!(primary(earth_position + DVec3::new(0.0, 2e8, 0.0)), earth);
+        assert_eq!(primary(DVec3::new(0.5 * AU, 0.0, 0.0)), sun);
+    }
+
+    #[test]
+    fn transition_sends_event() {
+        let mut app = App::new();
+        app.add_plugins(PhysicPlugin);
+        let earth = app
+            .world
+            .spawn((
+                NBodyActiveBundle::new(&DVec3::ZERO, 5.972e24),
+                FloatingOriginPosition(DVec3::ZERO),
+            ))
+            .id();
+        let moon_position = DVec3::new(MOON_DISTANCE, 0.0, 0.0);
+        let moon = app
+            .world
+            .spawn((
+                NBodyActiveBundle::new(&DVec3::ZERO, 7.342e22),
+                FloatingOriginPosition(moon_position),
+            ))
+            .id();
+        let craft = app
+            .world
+            .spawn((
+                FloatingOriginPosition(DVec3::new(7e6, 0.0, 0.0)),
+                PrimaryBody(Entity::PLACEHOLDER),
+            ))
+            .id();
+
+        let transitions = |app: &mut App| {
+            app.world.run_system_once(assign_primaries);
+            app.world
+                .resource_mut::<Events<PrimaryChangedEvent>>()
+                .drain()
+                .map(|event| (event.body, event.previous, event.current))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            transitions(&mut app),
+            vec![(craft, Entity::PLACEHOLDER, earth)]
+        );
+        assert!(transitions(&mut app).is_empty());
+
+        // Approaching the moon crosses into its sphere of influence
+        app.world
+            .get_mut::<FloatingOriginPosition>(craft)
+            .expect("")
+            .0 = moon_position - DVec3::new(1e7, 0.0, 0.0);
+        assert_eq!(transitions(&mut app), vec![(craft, earth, moon)]);
+        assert_eq!(app.world.get::<PrimaryBody>(craft).expect("").0, moon);
+    }
+}
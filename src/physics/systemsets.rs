@@ -5,4 +5,8 @@ pub enum PhysicsSet {
     All,
     Forces,
     Integration,
+    /// Runs after the integration, detects crafts hitting planet surfaces
+    Collision,
+    /// Runs after the integration, uses the updated positions to track sphere of influence changes
+    Primary,
 }
@@ -0,0 +1,159 @@
+use bevy::{
+    ecs::{
+        query::Without,
+        system::{Query, Res},
+    },
+    math::DVec3,
+};
+use physical_constants::STANDARD_ACCELERATION_OF_GRAVITY;
+
+use crate::{floatingorigin::components::FloatingOriginPosition, physics::components::*};
+
+use super::resources::{PhysicsElapsed, PhysicsStep};
+
+/// Sets throttle and direction of crafts while one of their scheduled burns is active
+pub fn execute_burn_schedule(
+    mut crafts: Query<(&BurnSchedule, &mut Propulsion)>,
+    elapsed: Res<PhysicsElapsed>,
+) {
+    for (schedule, mut propulsion) in crafts.iter_mut() {
+        let active = schedule.0.iter().find(|burn| {
+            burn.start <= elapsed.seconds && elapsed.seconds < burn.start + burn.duration
+        });
+
+        match active {
+            Some(burn) => {
+                propulsion.throttle = burn.throttle;
+                propulsion.direction = burn.direction;
+                propulsion.scheduled = true;
+            }
+            None => {
+                if propulsion.scheduled {
+                    propulsion.throttle = 0.0;
+                    propulsion.scheduled = false;
+                }
+            }
+        }
+    }
+}
+
+///
+/// Adds the thrust of all firing engines to the acceleration and burns the required fuel.
+/// The velocity change of a step follows the rocket equation dv = Isp g0 ln(m0 / m1).
+/// Running backwards refills the fuel burnt and takes back the velocity change,
+/// at most the fuel burnt since the start, also when the throttle is opened while rewinding.
+///
+pub fn apply_thrust(
+    mut crafts: Query<
+        (
+            &mut Propulsion,
+            &mut NBodyAcceleration,
+            &FloatingOriginPosition,
+            &NBodyVelocity,
+            &PrimaryBody,
+        ),
+        Without<Landed>,
+    >,
+    primaries: Query<(&FloatingOriginPosition, &NBodyVelocity)>,
+    step: PhysicsStep,
+) {
+    let step = step.seconds();
+
+    for (mut propulsion, mut acc, position, velocity, primary) in crafts.iter_mut() {
+        if propulsion.throttle <= 0.0
+            || (step > 0.0 && propulsion.fuel_mass <= 0.0)
+            || propulsion.thrust <= 0.0
+            || propulsion.dry_mass <= 0.0
+        {
+            continue;
+        }
+
+        // Orient relative to the primary, or absolute if there is none
+        let (rel_position, rel_velocity) = match primaries.get(primary.0) {
+            Ok((p_position, p_velocity)) => (position.0 - p_position.0, velocity.0 - p_velocity.0),
+            Err(_) => (position.0, velocity.0),
+        };
+        let direction = propulsion
+            .fixed_direction
+            .unwrap_or_else(|| propulsion.direction.to_vector(rel_position, rel_velocity));
+        if direction == DVec3::ZERO {
+            continue;
+        }
+
+        let exhaust_velocity = propulsion.isp * STANDARD_ACCELERATION_OF_GRAVITY;
+        let mass_flow = propulsion.thrust * propulsion.throttle / exhaust_velocity;
+        let burnt = if step > 0.0 {
+            (mass_flow * step).min(propulsion.fuel_mass)
+        } else {
+            // The delivered velocity change gives the mass before the first burn
+            let initial_mass =
+                propulsion.mass() * (propulsion.delivered_delta_v / exhaust_velocity).exp();
+            (mass_flow * step).max((propulsion.mass() - initial_mass).min(0.0))
+        };
+        if burnt == 0.0 {
+            continue;
+        }
+
+        let start_mass = propulsion.mass();
+        propulsion.fuel_mass -= burnt;
+        let delta_v = exhaust_velocity * (start_mass / propulsion.mass()).ln();
+        propulsion.delivered_delta_v += delta_v;
+
+        acc.0 += direction * delta_v / step;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::{app::App, ecs::entity::Entity};
+
+    use crate::physics::{
+        bundles::NBodyPassiveBundle,
+        resources::{PhysicsDirection, PhysicsTimestep},
+        step_physics, PhysicPlugin,
+    };
+
+    use super::*;
+
+    #[test]
+    fn refill_bounded_by_fuel_burnt() {
+        let mut app = App::new();
+        app.add_plugins(PhysicPlugin)
+            .insert_resource(PhysicsTimestep(1.0));
+        let craft = app
+            .world
+            .spawn((
+                NBodyPassiveBundle::new(&DVec3::new(0.0, 7668.0, 0.0)),
+                FloatingOriginPosition(DVec3::new(6.778e6, 0.0, 0.0)),
+                PrimaryBody(Entity::PLACEHOLDER),
+                Propulsion {
+                    dry_mass: 1000.0,
+                    fuel_mass: 500.0,
+                    thrust: 2000.0,
+                    isp: 300.0,
+                    throttle: 1.0,
+                    direction: ThrustDirection::Prograde,
+                    scheduled: false,
+                    fixed_direction: None,
+                    delivered_delta_v: 0.0,
+                },
+            ))
+            .id();
+
+        for _ in 0..10 {
+            step_physics(&mut app.world);
+        }
+        assert!(app.world.get::<Propulsion>(craft).expect("").fuel_mass < 500.0);
+
+        // The open throttle only refills what was burnt, however long it runs backwards
+        app.insert_resource(PhysicsDirection::Backward);
+        for _ in 0..50 {
+            step_physics(&mut app.world);
+        }
+        let propulsion = app.world.get::<Propulsion>(craft).expect("");
+        assert!((propulsion.fuel_mass - 500.0).abs() < 1e-9);
+        assert!(propulsion.delivered_delta_v.abs() < 1e-9);
+        let velocity = app.world.get::<NBodyVelocity>(craft).expect("").0;
+        assert!((velocity - DVec3::new(0.0, 7668.0, 0.0)).length() < 1e-9);
+    }
+}
@@ -0,0 +1,75 @@
+use bevy::{
+    core_pipeline::clear_color::ClearColor,
+    ecs::{
+        component::Component,
+        query::{With, Without},
+        system::{Commands, Query},
+    },
+    math::{Quat, Vec3},
+    pbr::{AmbientLight, DirectionalLight, DirectionalLightBundle},
+    render::{camera::Camera, color::Color},
+    transform::components::Transform,
+};
+
+use crate::{floatingorigin::components::FloatingOriginPosition, physics::components::Star};
+
+/// Marks the directional light standing in for the brightest star
+#[derive(Component)]
+pub struct StarLight;
+
+pub fn spawn_light(mut commands: Commands) {
+    commands.insert_resource(AmbientLight {
+        color: Color::BLACK,
+        brightness: 0.,
+    });
+
+    commands.insert_resource(ClearColor(Color::BLACK));
+
+    // Keeps this direction until a star is found
+    commands.spawn((
+        DirectionalLightBundle {
+            directional_light: DirectionalLight {
+                illuminance: 25000.,
+                shadows_enabled: true,
+                ..Default::default()
+            },
+            transform: Transform::from_xyz(-20000000.0, 0.0, 0.0)
+                .looking_at(Vec3::new(1.0, 0.0, 0.0), Vec3::Z),
+            ..Default::default()
+        },
+        StarLight,
+    ));
+}
+
+///
+/// Points the light from the star appearing brightest at the camera towards the camera.
+/// Stars are far away compared to the visible scene, so a single direction is a good approximation
+/// and day and night sides of planets and moons follow the star as time advances.
+///
+pub fn follow_star(
+    mut lights: Query<&mut Transform, With<StarLight>>,
+    camera: Query<&FloatingOriginPosition, With<Camera>>,
+    stars: Query<(&Star, &FloatingOriginPosition), Without<Camera>>,
+) {
+    let Ok(camera) = camera.get_single() else {
+        return;
+    };
+    let brightest = stars.iter().max_by(|(a, a_pos), (b, b_pos)| {
+        let a_flux = a.luminosity / a_pos.0.distance_squared(camera.0);
+        let b_flux = b.luminosity / b_pos.0.distance_squared(camera.0);
+        a_flux.total_cmp(&b_flux)
+    });
+    let Some((_, star_position)) = brightest else {
+        return;
+    };
+
+    let offset = star_position.0 - camera.0;
+    if offset.length_squared() == 0.0 {
+        return;
+    }
+    for mut light in lights.iter_mut() {
+        // Directional lights shine along their local -Z axis
+        light.translation = offset.as_vec3();
+        light.rotation = Quat::from_rotation_arc(Vec3::NEG_Z, -offset.normalize().as_vec3());
+    }
+}
@@ -19,7 +19,13 @@ use bevy::{
 #[derive(Component)]
 pub struct OrbitHistoryMesh {
     pub orbit_mesh: AssetId<Mesh>,
-    pub history: VecDeque<DVec3>,
+    /// Positions together with the simulated seconds they were recorded at
+    pub history: VecDeque<(f64, DVec3)>,
+}
+
+#[derive(Component)]
+pub struct OrbitPredictionMesh {
+    pub orbit_mesh: AssetId<Mesh>,
 }
 
 /// Material used for the line segments
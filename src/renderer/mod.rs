@@ -1,15 +1,32 @@
 use bevy::{
-    app::{App, Plugin},
+    app::{App, Plugin, Startup, Update},
+    ecs::schedule::IntoSystemConfigs,
     pbr::MaterialPlugin,
 };
 
+pub mod light;
 pub mod line;
 
-use crate::renderer::line::LineMaterial;
+use crate::{
+    floatingorigin::systemsets::FloatingOriginSet,
+    objects::systemsets::CameraSets,
+    renderer::{
+        light::{follow_star, spawn_light},
+        line::LineMaterial,
+    },
+};
 
+/// Registers the custom materials and spawns the light following the brightest star
 pub struct RendererPlugin;
 impl Plugin for RendererPlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugins(MaterialPlugin::<LineMaterial>::default());
+        app.add_plugins(MaterialPlugin::<LineMaterial>::default())
+            .add_systems(Startup, spawn_light)
+            .add_systems(
+                Update,
+                follow_star
+                    .after(CameraSets::CameraAll)
+                    .before(FloatingOriginSet::ApplyTransform),
+            );
     }
 }
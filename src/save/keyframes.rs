@@ -0,0 +1,281 @@
+use bevy::{
+    app::{App, Plugin, Update},
+    ecs::{
+        event::{Event, Events},
+        schedule::{
+            common_conditions::{not, resource_exists},
+            IntoSystemConfigs, IntoSystemSetConfigs,
+        },
+        system::Resource,
+        world::World,
+    },
+    log::{info, warn},
+};
+
+use crate::physics::{
+    resources::{PhysicsAccumulator, PhysicsDirection, PhysicsElapsed},
+    step_physics,
+    systemsets::PhysicsSet,
+    PhysicsSchedule,
+};
+
+use super::{capture, restore_snapshot, Snapshot};
+
+/// Keyframes kept at most, every second one is dropped and the interval doubled when exceeded
+const MAX_KEYFRAMES: usize = 512;
+
+/// Steps replayed per frame while seeking, so a long replay does not block the window
+const REPLAY_STEPS_PER_FRAME: u32 = 2000;
+
+/// Simulated seconds between two keyframes
+#[derive(Resource)]
+pub struct KeyframeInterval(pub f64);
+
+/// States recorded periodically while running forwards, ordered by time
+#[derive(Resource, Default)]
+pub struct Keyframes(pub Vec<Snapshot>);
+impl Keyframes {
+    /// Simulated seconds of the earliest moment which can be sought
+    pub fn earliest(&self) -> Option<f64> {
+        self.0.first().map(|keyframe| keyframe.elapsed)
+    }
+
+    /// Simulated seconds of the latest recorded keyframe
+    pub fn latest(&self) -> Option<f64> {
+        self.0.last().map(|keyframe| keyframe.elapsed)
+    }
+}
+
+/// Requests to seek to the given simulated seconds since the start
+#[derive(Event)]
+pub struct SeekEvent(pub f64);
+
+/// Simulated seconds a seek is replaying towards, the physics only runs the replay until they are reached
+#[derive(Resource)]
+pub struct SeekTarget(pub f64);
+
+///
+/// Records keyframes of the simulation state during the physics steps and seeks to earlier moments.
+/// Running forwards again after rewinding or seeking overwrites the keyframes after the current moment.
+///
+pub struct KeyframePlugin;
+impl Plugin for KeyframePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(KeyframeInterval(3600.0))
+            .insert_resource(Keyframes::default())
+            .add_event::<SeekEvent>()
+            .add_systems(PhysicsSchedule, record_keyframe.before(PhysicsSet::Forces))
+            .configure_sets(
+                Update,
+                PhysicsSet::All.run_if(not(resource_exists::<SeekTarget>())),
+            )
+            .add_systems(
+                Update,
+                (handle_seek_events, replay_seek)
+                    .chain()
+                    .before(PhysicsSet::All),
+            );
+    }
+}
+
+/// Records the state at the start of a forward step once the interval since the last keyframe has passed
+fn record_keyframe(world: &mut World) {
+    if *world.resource::<PhysicsDirection>() == PhysicsDirection::Backward {
+        return;
+    }
+    let now = world.resource::<PhysicsElapsed>().seconds;
+    let interval = world.resource::<KeyframeInterval>().0;
+
+    let mut keyframes = world.resource_mut::<Keyframes>();
+    keyframes.0.retain(|keyframe| keyframe.elapsed <= now);
+    if keyframes
+        .0
+        .last()
+        .is_some_and(|keyframe| now < keyframe.elapsed + interval)
+    {
+        return;
+    }
+
+    let keyframe = capture(world, false);
+    let mut keyframes = world.resource_mut::<Keyframes>();
+    keyframes.0.push(keyframe);
+    if keyframes.0.len() > MAX_KEYFRAMES {
+        let mut index = 0;
+        keyframes.0.retain(|_| {
+            index += 1;
+            index % 2 == 1
+        });
+        world.resource_mut::<KeyframeInterval>().0 = interval * 2.0;
+    }
+}
+
+/// Restores the keyframe of the last seek request, the replay to the target is spread over the next frames
+fn handle_seek_events(world: &mut World) {
+    let Some(SeekEvent(target)) = world.resource_mut::<Events<SeekEvent>>().drain().last() else {
+        return;
+    };
+    match restore_keyframe(world, target) {
+        Ok(()) => world.insert_resource(SeekTarget(target)),
+        Err(e) => warn!("Seeking failed: {}", e),
+    }
+}
+
+fn replay_seek(world: &mut World) {
+    let Some(&SeekTarget(target)) = world.get_resource::<SeekTarget>() else {
+        return;
+    };
+    if replay(world, target, REPLAY_STEPS_PER_FRAME) {
+        world.remove_resource::<SeekTarget>();
+        info!("Sought to {:.0} s", target);
+    }
+}
+
+///
+/// Restores the last keyframe before the target and steps forwards until the target is reached.
+/// The replay uses the same fixed steps from the complete recorded state, so the recorded course
+/// of events is reproduced exactly. Only planets respawned after a merge are summed up in another
+/// order than before, which changes the last bits of the accelerations.
+///
+pub fn seek(world: &mut World, target: f64) -> Result<(), String> {
+    restore_keyframe(world, target)?;
+    replay(world, target, u32::MAX);
+    Ok(())
+}
+
+fn restore_keyframe(world: &mut World, target: f64) -> Result<(), String> {
+    let Some(keyframe) = world
+        .resource::<Keyframes>()
+        .0
+        .iter()
+        .rev()
+        .find(|keyframe| keyframe.elapsed <= target)
+        .cloned()
+    else {
+        return Err(format!("No keyframe recorded before {:.0} s", target));
+    };
+    restore_snapshot(world, &keyframe)
+}
+
+/// Steps forwards towards the target, at most the given number of steps. Returns whether the target was reached.
+fn replay(world: &mut World, target: f64, max_steps: u32) -> bool {
+    let direction = *world.resource::<PhysicsDirection>();
+    world.insert_resource(PhysicsDirection::Forward);
+    let mut steps = 0;
+    while world.resource::<PhysicsElapsed>().seconds < target && steps < max_steps {
+        step_physics(world);
+        steps += 1;
+    }
+    world.insert_resource(direction);
+
+    let reached = world.resource::<PhysicsElapsed>().seconds >= target;
+    if reached {
+        world.resource_mut::<PhysicsAccumulator>().0 = 0.0;
+    }
+    reached
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::{
+        core::Name,
+        ecs::{entity::Entity, query::With},
+        math::DVec3,
+        MinimalPlugins,
+    };
+
+    use crate::{
+        objects::{components::Craft, scenario::Scenario, HeadlessObjectsPlugins},
+        physics::{
+            components::ManeuverNode,
+            resources::{ImpactPolicy, PhysicsTimeScale, PhysicsTimestep},
+            PhysicPlugin,
+        },
+        save::take_snapshot,
+    };
+
+    use super::*;
+
+    /// Earth with a craft in orbit and one falling onto the surface, where it is destroyed after about 165 s
+    const SCENARIO: &str = r#"{
+        "planets": [{
+            "name": "Earth",
+            "position": [0.0, 0.0, 0.0],
+            "velocity": [0.0, 0.0, 0.0],
+            "mass": 5.972e24,
+            "radius": 6371000,
+            "axial_tilt": 0.4,
+            "angular_velocity": 7.29e-5
+        }],
+        "crafts": [
+            { "name": "orbiter", "position": [6778000.0, 0.0, 0.0], "velocity": [0.0, 7668.0, 0.0] },
+            { "name": "faller", "position": [0.0, 6500000.0, 0.0], "velocity": [0.0, 0.0, 0.0] }
+        ]
+    }"#;
+
+    fn simulation() -> App {
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            HeadlessObjectsPlugins,
+            PhysicPlugin,
+            KeyframePlugin,
+        ))
+        .insert_resource(serde_json::from_str::<Scenario>(SCENARIO).expect(""))
+        .insert_resource(ImpactPolicy::Destroy)
+        .insert_resource(PhysicsTimestep(1.0))
+        .insert_resource(PhysicsTimeScale(1))
+        .insert_resource(KeyframeInterval(100.0));
+        app.finish();
+        app.cleanup();
+        app.update();
+        app
+    }
+
+    /// State with the bodies ordered by name, respawned bodies are iterated last
+    fn state(world: &mut World) -> String {
+        let mut snapshot = take_snapshot(world);
+        snapshot.bodies.sort_by(|a, b| a.name.cmp(&b.name));
+        serde_json::to_string(&snapshot).expect("")
+    }
+
+    fn run_until(world: &mut World, seconds: f64) {
+        while world.resource::<PhysicsElapsed>().seconds < seconds {
+            step_physics(world);
+        }
+    }
+
+    #[test]
+    fn seek_restores_removed_bodies_and_nodes() {
+        let mut app = simulation();
+        let orbiter = app
+            .world
+            .query_filtered::<(Entity, &Name), With<Craft>>()
+            .iter(&app.world)
+            .find(|(_, name)| name.as_str() == "orbiter")
+            .map(|(entity, _)| entity)
+            .expect("");
+        let mut node = ManeuverNode::new(150.0);
+        node.delta_v = DVec3::new(10.0, 0.0, 0.0);
+        app.world.entity_mut(orbiter).insert(node);
+
+        run_until(&mut app.world, 120.0);
+        let before = state(&mut app.world);
+        run_until(&mut app.world, 400.0);
+        let after = state(&mut app.world);
+        let mut crafts = app.world.query_filtered::<&Name, With<Craft>>();
+        assert_eq!(crafts.iter(&app.world).count(), 1);
+        assert!(app
+            .world
+            .query::<&ManeuverNode>()
+            .iter(&app.world)
+            .next()
+            .is_none());
+
+        // The destroyed craft and the executed node are back, the replay ends up in the same state
+        seek(&mut app.world, 120.0).expect("");
+        assert_eq!(crafts.iter(&app.world).count(), 2);
+        assert_eq!(before, state(&mut app.world));
+        run_until(&mut app.world, 400.0);
+        assert_eq!(after, state(&mut app.world));
+    }
+}
@@ -0,0 +1,620 @@
+pub mod keyframes;
+
+use std::{
+    collections::{HashMap, HashSet},
+    fs::{create_dir_all, read_to_string, write},
+    path::{Path, PathBuf},
+};
+
+use bevy::{
+    app::{App, Plugin, Startup, Update},
+    asset::{Assets, Handle},
+    core::Name,
+    ecs::{
+        entity::Entity,
+        query::With,
+        schedule::{apply_deferred, IntoSystemConfigs},
+        system::Resource,
+        world::World,
+    },
+    hierarchy::despawn_with_children_recursive,
+    input::{keyboard::KeyCode, Input},
+    log::{info, warn},
+    math::DVec3,
+    render::mesh::{shape::UVSphere, Mesh},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    floatingorigin::components::FloatingOriginPosition,
+    objects::{
+        components::Focusable,
+        planet::components::Planet,
+        respawn::{respawn_bodies, BodyDefinitions},
+        systemsets::ObjectSets,
+    },
+    orbits::{
+        history::{OrbitHistoryEntity, SelectedReferenceFrame},
+        prediction::OrbitPredictionEntity,
+    },
+    physics::{
+        components::{
+            BurnSchedule, Landed, ManeuverNode, MassG, NBodyEffector, NBodyVelocity, PrimaryBody,
+            Propulsion, ScheduledBurn, ThrustDirection,
+        },
+        epoch::Epoch,
+        resources::{IntegratorSubstep, PhysicsAccumulator, PhysicsElapsed},
+        systemsets::PhysicsSet,
+    },
+    renderer::line::OrbitHistoryMesh,
+    save::keyframes::{Keyframes, SeekTarget},
+    utils::data::{get_data_dir, DataDir},
+};
+
+/// Format version of the snapshot files, snapshots of other versions are rejected
+const SNAPSHOT_VERSION: u32 = 1;
+
+/// Snapshot written and read by the keyboard shortcuts
+const QUICKSAVE_NAME: &str = "quicksave.json";
+
+/// Snapshot restored once all bodies have been spawned
+#[derive(Resource)]
+pub struct InitialSnapshot(pub PathBuf);
+
+#[derive(Serialize, Deserialize, Clone)]
+struct PlanetSnapshot {
+    spin_position: f64,
+    radius: f64,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct PropulsionSnapshot {
+    fuel_mass: f64,
+    throttle: f64,
+    direction: ThrustDirection,
+    scheduled: bool,
+    fixed_direction: Option<[f64; 3]>,
+    delivered_delta_v: f64,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct ManeuverSnapshot {
+    time: f64,
+    delta_v: [f64; 3],
+    burn: Option<[f64; 3]>,
+    burn_start: f64,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct LandedSnapshot {
+    planet: String,
+    surface: [f64; 3],
+    time: f64,
+    velocity: [f64; 3],
+}
+
+/// Dynamic state of a single body, entities are referred to by name
+#[derive(Serialize, Deserialize, Clone)]
+struct BodySnapshot {
+    name: String,
+    position: [f64; 3],
+    velocity: [f64; 3],
+    mass_g: Option<f64>,
+    primary: Option<String>,
+    planet: Option<PlanetSnapshot>,
+    propulsion: Option<PropulsionSnapshot>,
+    maneuver: Option<ManeuverSnapshot>,
+    burns: Option<Vec<ScheduledBurn>>,
+    landed: Option<LandedSnapshot>,
+    /// Simulated seconds and absolute positions of the orbit history, empty without rendering.
+    /// Keyframes leave it out, the history is only trimmed when seeking.
+    history: Option<Vec<(f64, [f64; 3])>>,
+}
+
+///
+/// Dynamic state of a running simulation. Static properties like radii of unmerged planets or engines
+/// are not part of it, so a snapshot is restored onto the bodies spawned from the same scenario.
+/// Bodies removed by a collision since are spawned again from their definitions.
+///
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Snapshot {
+    version: u32,
+    elapsed: f64,
+    steps: u64,
+    /// Start epoch in TDB seconds past J2000
+    start: f64,
+    accumulator: f64,
+    substep_size: f64,
+    substep_count: u32,
+    /// Planet the orbit histories are drawn relative to
+    reference_frame: Option<String>,
+    bodies: Vec<BodySnapshot>,
+}
+
+/// Quicksave with F5 and quickload with F9, restores the snapshot given on the command line at startup
+pub struct SavePlugin;
+impl Plugin for SavePlugin {
+    fn build(&self, app: &mut App) {
+        // Apply deferred to ensure all bodies have been created
+        app.add_systems(
+            Startup,
+            (apply_deferred, load_initial_snapshot)
+                .chain()
+                .after(ObjectSets::SpawnCraft)
+                .after(ObjectSets::ApplyScenario),
+        )
+        .add_systems(Update, quicksave_keys.before(PhysicsSet::All));
+    }
+}
+
+fn quicksave_path() -> PathBuf {
+    get_data_dir(DataDir::Saves).join(QUICKSAVE_NAME)
+}
+
+fn load_initial_snapshot(world: &mut World) {
+    let Some(InitialSnapshot(path)) = world.remove_resource::<InitialSnapshot>() else {
+        return;
+    };
+    match load_snapshot(world, &path) {
+        Ok(()) => info!("Loaded snapshot {}", path.display()),
+        Err(e) => panic!("Unable to load snapshot {}: {}", path.display(), e),
+    }
+}
+
+fn quicksave_keys(world: &mut World) {
+    let Some(keys) = world.get_resource::<Input<KeyCode>>() else {
+        return;
+    };
+    let (save, load) = (
+        keys.just_pressed(KeyCode::F5),
+        keys.just_pressed(KeyCode::F9),
+    );
+
+    let path = quicksave_path();
+    if save {
+        match write_snapshot(&path, &take_snapshot(world)) {
+            Ok(()) => info!("Quicksaved to {}", path.display()),
+            Err(e) => warn!("Quicksave failed: {}", e),
+        }
+    }
+    if load {
+        match load_snapshot(world, &path) {
+            Ok(()) => info!("Quickloaded {}", path.display()),
+            Err(e) => warn!("Quickload failed: {}", e),
+        }
+    }
+}
+
+/// Restores a snapshot file, the keyframes recorded so far and a running seek belong to another course of events
+fn load_snapshot(world: &mut World, path: &Path) -> Result<(), String> {
+    let snapshot = read_snapshot(path)?;
+    restore_snapshot(world, &snapshot)?;
+    if let Some(mut keyframes) = world.get_resource_mut::<Keyframes>() {
+        keyframes.0.clear();
+    }
+    world.remove_resource::<SeekTarget>();
+    Ok(())
+}
+
+pub fn write_snapshot(path: &Path, snapshot: &Snapshot) -> Result<(), String> {
+    let contents = serde_json::to_string_pretty(snapshot).expect("");
+    if let Some(dir) = path.parent() {
+        create_dir_all(dir).map_err(|e| format!("Unable to create {}: {}", dir.display(), e))?;
+    }
+    write(path, contents).map_err(|e| format!("Unable to write {}: {}", path.display(), e))
+}
+
+pub fn read_snapshot(path: &Path) -> Result<Snapshot, String> {
+    let contents =
+        read_to_string(path).map_err(|e| format!("Unable to read {}: {}", path.display(), e))?;
+    let snapshot: Snapshot = serde_json::from_str(&contents)
+        .map_err(|e| format!("Unable to parse {}: {}", path.display(), e))?;
+    if snapshot.version != SNAPSHOT_VERSION {
+        return Err(format!(
+            "Snapshot version {} is not supported, expected {}",
+            snapshot.version, SNAPSHOT_VERSION
+        ));
+    }
+    Ok(snapshot)
+}
+
+/// Captures the physics clock and the dynamic state of all bodies
+pub fn take_snapshot(world: &mut World) -> Snapshot {
+    capture(world, true)
+}
+
+fn capture(world: &mut World, with_history: bool) -> Snapshot {
+    let names: HashMap<Entity, String> = world
+        .query::<(Entity, &Name)>()
+        .iter(world)
+        .map(|(entity, name)| (entity, name.to_string()))
+        .collect();
+    let name = |entity: Entity| names.get(&entity).cloned();
+
+    let mut histories_q = world.query::<&OrbitHistoryMesh>();
+    let mut bodies_q = world.query_filtered::<(
+        &Name,
+        &FloatingOriginPosition,
+        &NBodyVelocity,
+        Option<&MassG>,
+        Option<&PrimaryBody>,
+        Option<&Planet>,
+        Option<&Propulsion>,
+        Option<&ManeuverNode>,
+        Option<&BurnSchedule>,
+        Option<&Landed>,
+        Option<&OrbitHistoryEntity>,
+    ), With<NBodyEffector>>();
+
+    let bodies = bodies_q
+        .iter(world)
+        .map(
+            |(
+                body,
+                position,
+                velocity,
+                mass,
+                primary,
+                planet,
+                propulsion,
+                maneuver,
+                burns,
+                landed,
+                history,
+            )| {
+                BodySnapshot {
+                    name: body.to_string(),
+                    position: position.0.to_array(),
+                    velocity: velocity.0.to_array(),
+                    mass_g: mass.map(|m| m.0),
+                    primary: primary.and_then(|p| name(p.0)),
+                    planet: planet.map(|p| PlanetSnapshot {
+                        spin_position: p.spin_position,
+                        radius: p.radius,
+                    }),
+                    propulsion: propulsion.map(|p| PropulsionSnapshot {
+                        fuel_mass: p.fuel_mass,
+                        throttle: p.throttle,
+                        direction: p.direction,
+                        scheduled: p.scheduled,
+                        fixed_direction: p.fixed_direction.map(|d| d.to_array()),
+                        delivered_delta_v: p.delivered_delta_v,
+                    }),
+                    maneuver: maneuver.map(|m| ManeuverSnapshot {
+                        time: m.time,
+                        delta_v: m.delta_v.to_array(),
+                        burn: m.burn.map(|b| b.to_array()),
+                        burn_start: m.burn_start,
+                    }),
+                    burns: burns.map(|b| b.0.clone()),
+                    landed: landed.and_then(|l| {
+                        name(l.planet).map(|planet| LandedSnapshot {
+                            planet,
+                            surface: l.surface.to_array(),
+                            time: l.time,
+                            velocity: l.velocity.to_array(),
+                        })
+                    }),
+                    history: with_history.then(|| {
+                        history
+                            .and_then(|h| histories_q.get(world, h.0).ok())
+                            .map_or(Vec::new(), |h| {
+                                h.history.iter().map(|(t, p)| (*t, p.to_array())).collect()
+                            })
+                    }),
+                }
+            },
+        )
+        .collect();
+
+    let reference_frame = world
+        .get_resource::<SelectedReferenceFrame>()
+        .map(|frame| frame.target)
+        .and_then(|target| {
+            world
+                .query::<(&Planet, &OrbitHistoryEntity)>()
+                .iter(world)
+                .find(|(_, history)| history.0 == target)
+                .map(|(planet, _)| planet.name.clone())
+        });
+
+    let elapsed = world.resource::<PhysicsElapsed>();
+    let substep = world.resource::<IntegratorSubstep>();
+    Snapshot {
+        version: SNAPSHOT_VERSION,
+        elapsed: elapsed.seconds,
+        steps: elapsed.steps,
+        start: elapsed.start.0,
+        accumulator: world.resource::<PhysicsAccumulator>().0,
+        substep_size: substep.size,
+        substep_count: substep.count,
+        reference_frame,
+        bodies,
+    }
+}
+
+///
+/// Restores a snapshot onto the bodies with the same names. Bodies missing in the snapshot,
+/// because they were destroyed or merged before it was taken, are despawned.
+/// Bodies of the snapshot removed since are respawned from their definitions,
+/// fails without changes if a body of the snapshot was never spawned.
+///
+pub fn restore_snapshot(world: &mut World, snapshot: &Snapshot) -> Result<(), String> {
+    let mut entities = body_entities(world);
+
+    let missing: Vec<String> = snapshot
+        .bodies
+        .iter()
+        .map(|body| body.name.clone())
+        .filter(|name| !entities.contains_key(name))
+        .collect();
+    let definitions = world.get_resource::<BodyDefinitions>();
+    let unknown: Vec<&str> = missing
+        .iter()
+        .map(String::as_str)
+        .filter(|name| !definitions.is_some_and(|d| d.contains(name)))
+        .collect();
+    if !unknown.is_empty() {
+        return Err(format!(
+            "No bodies named {}, the snapshot belongs to another scenario",
+            unknown.join(", ")
+        ));
+    }
+    if !missing.is_empty() {
+        respawn_bodies(world, &missing);
+        entities = body_entities(world);
+    }
+
+    // Remove bodies which no longer existed, together with their lines
+    let kept: HashSet<&str> = snapshot.bodies.iter().map(|b| b.name.as_str()).collect();
+    for (name, entity) in entities.iter() {
+        if kept.contains(name.as_str()) {
+            continue;
+        }
+        let body = world.entity(*entity);
+        let lines = [
+            body.get::<OrbitHistoryEntity>().map(|h| h.0),
+            body.get::<OrbitPredictionEntity>().map(|p| p.0),
+        ];
+        for line in lines.into_iter().flatten() {
+            if line != Entity::PLACEHOLDER && world.get_entity(line).is_some() {
+                despawn_with_children_recursive(world, line);
+            }
+        }
+        info!("Removed {}, it did not exist in the snapshot", name);
+        despawn_with_children_recursive(world, *entity);
+    }
+
+    let find = |name: &Option<String>| {
+        name.as_ref()
+            .and_then(|name| entities.get(name))
+            .copied()
+            .unwrap_or(Entity::PLACEHOLDER)
+    };
+    for body in snapshot.bodies.iter() {
+        let entity = entities[&body.name];
+        let mut state = world.entity_mut(entity);
+        if let Some(mut position) = state.get_mut::<FloatingOriginPosition>() {
+            position.0 = DVec3::from_array(body.position);
+        }
+        if let Some(mut velocity) = state.get_mut::<NBodyVelocity>() {
+            velocity.0 = DVec3::from_array(body.velocity);
+        }
+        if let (Some(mut mass), Some(mass_g)) = (state.get_mut::<MassG>(), body.mass_g) {
+            mass.0 = mass_g;
+        }
+        if let Some(mut primary) = state.get_mut::<PrimaryBody>() {
+            primary.0 = find(&body.primary);
+        }
+        match &body.maneuver {
+            Some(maneuver) => {
+                state.insert(ManeuverNode {
+                    time: maneuver.time,
+                    delta_v: DVec3::from_array(maneuver.delta_v),
+                    burn: maneuver.burn.map(DVec3::from_array),
+                    burn_start: maneuver.burn_start,
+                });
+            }
+            None => {
+                state.remove::<ManeuverNode>();
+            }
+        }
+        if let (Some(mut schedule), Some(burns)) = (state.get_mut::<BurnSchedule>(), &body.burns) {
+            schedule.0 = burns.clone();
+        }
+        if let (Some(mut propulsion), Some(saved)) =
+            (state.get_mut::<Propulsion>(), &body.propulsion)
+        {
+            propulsion.fuel_mass = saved.fuel_mass;
+            propulsion.throttle = saved.throttle;
+            propulsion.direction = saved.direction;
+            propulsion.scheduled = saved.scheduled;
+            propulsion.fixed_direction = saved.fixed_direction.map(DVec3::from_array);
+            propulsion.delivered_delta_v = saved.delivered_delta_v;
+        }
+        match &body.landed {
+            Some(landed) => {
+                state.insert(Landed {
+                    planet: find(&Some(landed.planet.clone())),
+                    surface: DVec3::from_array(landed.surface),
+                    time: landed.time,
+                    velocity: DVec3::from_array(landed.velocity),
+                });
+            }
+            None => {
+                state.remove::<Landed>();
+            }
+        }
+        if let Some(saved) = &body.planet {
+            restore_planet(world, entity, saved);
+        }
+
+        let history = world.get::<OrbitHistoryEntity>(entity).map(|h| h.0);
+        if let (Some(mut mesh), Some(saved)) = (
+            history.and_then(|h| world.get_mut::<OrbitHistoryMesh>(h)),
+            &body.history,
+        ) {
+            mesh.history = saved
+                .iter()
+                .map(|(time, position)| (*time, DVec3::from_array(*position)))
+                .collect();
+        }
+    }
+
+    let mut elapsed = world.resource_mut::<PhysicsElapsed>();
+    elapsed.seconds = snapshot.elapsed;
+    elapsed.steps = snapshot.steps;
+    elapsed.start = Epoch(snapshot.start);
+    world.resource_mut::<PhysicsAccumulator>().0 = snapshot.accumulator;
+    *world.resource_mut::<IntegratorSubstep>() = IntegratorSubstep {
+        size: snapshot.substep_size,
+        count: snapshot.substep_count,
+    };
+
+    let reference = find(&snapshot.reference_frame);
+    let target = world
+        .get::<OrbitHistoryEntity>(reference)
+        .map_or(Entity::PLACEHOLDER, |h| h.0);
+    if let Some(mut frame) = world.get_resource_mut::<SelectedReferenceFrame>() {
+        frame.target = target;
+    }
+    Ok(())
+}
+
+/// Entities of all bodies by name
+fn body_entities(world: &mut World) -> HashMap<String, Entity> {
+    world
+        .query_filtered::<(Entity, &Name), With<NBodyEffector>>()
+        .iter(world)
+        .map(|(entity, name)| (name.to_string(), entity))
+        .collect()
+}
+
+/// Restores spin and radius of a planet, the radius only differs after a merge
+fn restore_planet(world: &mut World, entity: Entity, saved: &PlanetSnapshot) {
+    let mut state = world.entity_mut(entity);
+    let mut planet = state.get_mut::<Planet>().expect("");
+    planet.spin_position = saved.spin_position;
+    if planet.radius == saved.radius {
+        return;
+    }
+    planet.radius = saved.radius;
+
+    if let Some(mut focus) = state.get_mut::<Focusable>() {
+        focus.focus_sphere_radius = saved.radius;
+        focus.focus_min_distance = saved.radius * 1.006;
+    }
+    let mesh = state.get::<Handle<Mesh>>().map(|m| m.id());
+    if let (Some(mesh), Some(mut meshes)) = (mesh, world.get_resource_mut::<Assets<Mesh>>()) {
+        meshes.insert(
+            mesh,
+            Mesh::from(UVSphere {
+                radius: saved.radius as f32,
+                sectors: 64,
+                stacks: 64,
+            }),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env::temp_dir, process::id};
+
+    use bevy::{app::App, core::Name, ecs::entity::Entity, math::DVec3};
+
+    use crate::{
+        floatingorigin::components::FloatingOriginPosition,
+        objects::{components::Craft, planet::components::Planet},
+        physics::{
+            bundles::{NBodyActiveBundle, NBodyPassiveBundle},
+            components::{BurnSchedule, PrimaryBody, Propulsion, ScheduledBurn, ThrustDirection},
+            resources::PhysicsTimestep,
+            step_physics, PhysicPlugin,
+        },
+    };
+
+    use super::*;
+
+    /// Earth and a craft in low orbit, which burns its engine between 30 s and 90 s
+    fn simulation() -> App {
+        let mut app = App::new();
+        app.add_plugins(PhysicPlugin)
+            .insert_resource(PhysicsTimestep(1.0));
+        app.world.spawn((
+            Name::new("Earth"),
+            Planet {
+                axial_tilt: 0.4,
+                spin_velocity: 7.29e-5,
+                spin_position: 0.0,
+                name: "Earth".into(),
+                radius: 6.371e6,
+            },
+            NBodyActiveBundle::new(&DVec3::ZERO, 5.972e24),
+            FloatingOriginPosition(DVec3::ZERO),
+        ));
+        app.world.spawn((
+            Name::new("Craft"),
+            Craft,
+            NBodyPassiveBundle::new(&DVec3::new(0.0, 7668.0, 0.0)),
+            FloatingOriginPosition(DVec3::new(6.778e6, 0.0, 0.0)),
+            PrimaryBody(Entity::PLACEHOLDER),
+            Propulsion {
+                dry_mass: 1000.0,
+                fuel_mass: 500.0,
+                thrust: 2000.0,
+                isp: 300.0,
+                throttle: 0.0,
+                direction: ThrustDirection::default(),
+                scheduled: false,
+                fixed_direction: None,
+                delivered_delta_v: 0.0,
+            },
+            BurnSchedule(vec![ScheduledBurn {
+                start: 30.0,
+                duration: 60.0,
+                throttle: 1.0,
+                direction: ThrustDirection::Prograde,
+            }]),
+        ));
+        app
+    }
+
+    fn state(world: &mut World) -> String {
+        serde_json::to_string(&take_snapshot(world)).expect("")
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut app = simulation();
+        for _ in 0..60 {
+            step_physics(&mut app.world);
+        }
+        let saved = state(&mut app.world);
+        let path = temp_dir().join(format!("bevy-universe-snapshot-{}.json", id()));
+        write_snapshot(&path, &take_snapshot(&mut app.world)).expect("");
+
+        for _ in 0..60 {
+            step_physics(&mut app.world);
+        }
+        let continued = state(&mut app.world);
+
+        // Restoring brings back the saved state and the run continues exactly as before
+        let snapshot = read_snapshot(&path).expect("");
+        std::fs::remove_file(&path).expect("");
+        restore_snapshot(&mut app.world, &snapshot).expect("");
+        assert_eq!(saved, state(&mut app.world));
+        for _ in 0..60 {
+            step_physics(&mut app.world);
+        }
+        assert_eq!(continued, state(&mut app.world));
+
+        // A fresh simulation resumes from the file just the same
+        let mut resumed = simulation();
+        restore_snapshot(&mut resumed.world, &snapshot).expect("");
+        for _ in 0..60 {
+            step_physics(&mut resumed.world);
+        }
+        assert_eq!(continued, state(&mut resumed.world));
+    }
+}
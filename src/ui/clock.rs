@@ -3,15 +3,15 @@ use bevy::{
     asset::AssetServer,
     ecs::{
         component::Component,
+        query::With,
         system::{Commands, Query, Res},
     },
     text::Text,
     text::TextStyle,
-    time::Time,
     ui::{node_bundles::TextBundle, FlexDirection, Style, UiRect, Val},
 };
 
-use crate::physics::resources::{PhysicsStepScale, PhysicsTimeScale};
+use crate::physics::{epoch::TimeScale, resources::PhysicsElapsed};
 
 use super::{container::UiContainerBuilder, window::UiWindowBuilder};
 
@@ -20,10 +20,7 @@ const SEC_PER_HOUR: f64 = 3600.0;
 const SEC_PER_MIN: f64 = 60.0;
 
 #[derive(Component)]
-struct SimSpeedChange(u16);
-
-#[derive(Component)]
-struct Clock(f64);
+struct Clock;
 
 pub struct UiClockPlugin;
 
@@ -49,7 +46,7 @@ pub fn build_ui(mut commands: Commands, asset_server: Res<AssetServer>) {
                 margin: UiRect::right(Val::Px(5.)),
                 ..Default::default()
             }),
-            Clock(0.0),
+            Clock,
         ))
         .id();
 
@@ -64,17 +61,12 @@ pub fn build_ui(mut commands: Commands, asset_server: Res<AssetServer>) {
     );
 }
 
-fn update_time(
-    mut display: Query<(&mut Clock, &mut Text)>,
-    speed_scale: Res<PhysicsTimeScale>,
-    step_scale: Res<PhysicsStepScale>,
-    time: Res<Time>,
-) {
-    let time_passed = time.delta_seconds_f64() * (speed_scale.0 * step_scale.0) as f64;
-    let (mut clock, mut text) = display.get_single_mut().expect("");
-    clock.0 += time_passed;
+/// Shows the current epoch of the physics clock in UTC and TDB and the time since the start of the simulation
+fn update_time(mut display: Query<&mut Text, With<Clock>>, elapsed: Res<PhysicsElapsed>) {
+    let mut text = display.get_single_mut().expect("");
+    let now = elapsed.now();
 
-    let mut secs = clock.0;
+    let mut secs = elapsed.seconds;
     let days = (secs / SEC_PER_DAY).floor();
     secs -= days * SEC_PER_DAY;
 
@@ -86,5 +78,10 @@ fn update_time(
 
     secs = secs.floor();
 
-    text.sections[0].value = format!("{days}:{hours}:{mins}:{secs}");
+    text.sections[0].value = format!(
+        "{}\n{}\nJD {:.5} TDB\nT+{days}:{hours}:{mins}:{secs}",
+        now.format(TimeScale::Utc),
+        now.format(TimeScale::Tdb),
+        now.julian_date(TimeScale::Tdb)
+    );
 }
@@ -0,0 +1,137 @@
+use bevy::{
+    app::{App, Plugin, Startup, Update},
+    asset::AssetServer,
+    ecs::{
+        component::Component,
+        entity::Entity,
+        query::{Changed, With, Without},
+        system::{Commands, Query, Res, ResMut},
+    },
+    log::info,
+    text::Text,
+    text::TextStyle,
+    ui::{node_bundles::TextBundle, FlexDirection, Interaction, Style, UiRect, Val},
+};
+
+use crate::physics::resources::{Integrator, IntegratorSubstep};
+
+use super::{
+    button::{UiButtonBuilder, UiButtonStyle},
+    container::UiContainerBuilder,
+    window::UiWindowBuilder,
+};
+
+#[derive(Component)]
+struct IntegratorChange(Integrator);
+
+#[derive(Component)]
+struct IntegratorDisplay;
+
+#[derive(Component)]
+struct SubstepDisplay;
+
+pub struct UiIntegratorPlugin;
+
+impl Plugin for UiIntegratorPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, build_ui)
+            .add_systems(Update, (change_integrator, update_substep));
+    }
+}
+
+pub fn build_ui(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    integrator: Res<Integrator>,
+) {
+    let buttons: Vec<Entity> = Integrator::ALL
+        .iter()
+        .map(|i| {
+            UiButtonBuilder::build(
+                &mut commands,
+                &asset_server,
+                IntegratorChange(*i),
+                i.name().into(),
+                UiButtonStyle::default(),
+            )
+        })
+        .collect();
+
+    let button_container =
+        UiContainerBuilder::build(&mut commands, FlexDirection::Row, buttons.as_slice());
+
+    let current = commands
+        .spawn((
+            TextBundle::from_section(
+                integrator.name(),
+                TextStyle {
+                    font: asset_server.load("fonts/Consolas.ttf"),
+                    font_size: 20.0,
+                    ..Default::default()
+                },
+            )
+            .with_style(Style {
+                margin: UiRect::right(Val::Px(5.)),
+                ..Default::default()
+            }),
+            IntegratorDisplay,
+        ))
+        .id();
+
+    let substep = commands
+        .spawn((
+            TextBundle::from_section(
+                "",
+                TextStyle {
+                    font: asset_server.load("fonts/Consolas.ttf"),
+                    font_size: 15.0,
+                    ..Default::default()
+                },
+            ),
+            SubstepDisplay,
+        ))
+        .id();
+
+    let selection = UiContainerBuilder::build(
+        &mut commands,
+        FlexDirection::Row,
+        &[current, button_container],
+    );
+
+    let container =
+        UiContainerBuilder::build(&mut commands, FlexDirection::Column, &[selection, substep]);
+
+    UiWindowBuilder::build(
+        &mut commands,
+        &asset_server,
+        "Integrator".into(),
+        container,
+        (10.0, 400.0),
+    );
+}
+
+#[allow(clippy::type_complexity)]
+fn change_integrator(
+    interaction_query: Query<
+        (&Interaction, &IntegratorChange),
+        (Changed<Interaction>, With<IntegratorChange>),
+    >,
+    mut display: Query<&mut Text, (With<IntegratorDisplay>, Without<IntegratorChange>)>,
+    mut integrator: ResMut<Integrator>,
+) {
+    for (interaction, integrator_change) in interaction_query.iter() {
+        if *interaction == Interaction::Pressed {
+            info!("Switching integrator to {}", integrator_change.0.name());
+            *integrator = integrator_change.0;
+            display.get_single_mut().expect("").sections[0].value = integrator.name().to_string();
+        }
+    }
+}
+
+fn update_substep(
+    mut display: Query<&mut Text, With<SubstepDisplay>>,
+    substep: Res<IntegratorSubstep>,
+) {
+    display.get_single_mut().expect("").sections[0].value =
+        format!("Step {:.3e} s x{}", substep.size, substep.count);
+}
@@ -0,0 +1,219 @@
+use bevy::{
+    app::{App, Plugin, Startup, Update},
+    asset::AssetServer,
+    ecs::{
+        component::Component,
+        entity::Entity,
+        query::{Changed, With},
+        schedule::IntoSystemConfigs,
+        system::{Commands, Query, Res},
+    },
+    log::info,
+    math::DVec3,
+    text::Text,
+    text::TextStyle,
+    ui::{node_bundles::TextBundle, FlexDirection, Interaction},
+};
+
+use crate::{
+    objects::components::FocusTarget,
+    physics::{
+        components::{ManeuverNode, Propulsion},
+        resources::PhysicsElapsed,
+        systemsets::PhysicsSet,
+    },
+};
+
+use super::{
+    button::{UiButtonBuilder, UiButtonStyle},
+    container::UiContainerBuilder,
+    systemsets::UiSets,
+    window::UiWindowBuilder,
+};
+
+/// Time ahead of the current simulated time at which new nodes are placed
+const NEW_NODE_DELAY: f64 = 600.0;
+/// Velocity change per button press in m/s
+const DELTA_V_STEP: f64 = 10.0;
+
+#[derive(Component, Clone, Copy)]
+enum ManeuverEdit {
+    Add,
+    Delete,
+    Time(f64),
+    DeltaV(DVec3),
+}
+
+#[derive(Component)]
+struct ManeuverDisplay;
+
+pub struct UiManeuverPlugin;
+
+impl Plugin for UiManeuverPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, build_ui).add_systems(
+            Update,
+            (
+                edit_maneuver
+                    .in_set(UiSets::UiUpdateAll)
+                    .before(PhysicsSet::All),
+                update_display,
+            ),
+        );
+    }
+}
+
+pub fn build_ui(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let row = |commands: &mut Commands, edits: &[(ManeuverEdit, &str)]| {
+        let buttons: Vec<Entity> = edits
+            .iter()
+            .map(|(edit, label)| {
+                UiButtonBuilder::build(
+                    commands,
+                    &asset_server,
+                    *edit,
+                    label.to_string(),
+                    UiButtonStyle::default(),
+                )
+            })
+            .collect();
+        UiContainerBuilder::build(commands, FlexDirection::Row, buttons.as_slice())
+    };
+
+    let node_row = row(
+        &mut commands,
+        &[(ManeuverEdit::Add, "Add"), (ManeuverEdit::Delete, "Delete")],
+    );
+    let time_row = row(
+        &mut commands,
+        &[
+            (ManeuverEdit::Time(-600.0), "-10m"),
+            (ManeuverEdit::Time(-60.0), "-1m"),
+            (ManeuverEdit::Time(60.0), "+1m"),
+            (ManeuverEdit::Time(600.0), "+10m"),
+        ],
+    );
+    let delta_v_row = row(
+        &mut commands,
+        &[
+            (ManeuverEdit::DeltaV(DVec3::X * DELTA_V_STEP), "Pro+"),
+            (ManeuverEdit::DeltaV(-DVec3::X * DELTA_V_STEP), "Pro-"),
+            (ManeuverEdit::DeltaV(DVec3::Y * DELTA_V_STEP), "Nrm+"),
+            (ManeuverEdit::DeltaV(-DVec3::Y * DELTA_V_STEP), "Nrm-"),
+            (ManeuverEdit::DeltaV(DVec3::Z * DELTA_V_STEP), "Rad+"),
+            (ManeuverEdit::DeltaV(-DVec3::Z * DELTA_V_STEP), "Rad-"),
+        ],
+    );
+
+    let display = commands
+        .spawn((
+            TextBundle::from_section(
+                "",
+                TextStyle {
+                    font: asset_server.load("fonts/Consolas.ttf"),
+                    font_size: 15.0,
+                    ..Default::default()
+                },
+            ),
+            ManeuverDisplay,
+        ))
+        .id();
+
+    let container = UiContainerBuilder::build(
+        &mut commands,
+        FlexDirection::Column,
+        &[display, node_row, time_row, delta_v_row],
+    );
+
+    UiWindowBuilder::build(
+        &mut commands,
+        &asset_server,
+        "Maneuver".into(),
+        container,
+        (400.0, 200.0),
+    );
+}
+
+/// Adds, removes and edits the maneuver node of the focused craft, nodes can not be edited while burning
+#[allow(clippy::type_complexity)]
+fn edit_maneuver(
+    mut commands: Commands,
+    interaction_query: Query<
+        (&Interaction, &ManeuverEdit),
+        (Changed<Interaction>, With<ManeuverEdit>),
+    >,
+    camera: Query<&FocusTarget>,
+    mut crafts: Query<(Option<&mut ManeuverNode>, &mut Propulsion)>,
+    elapsed: Res<PhysicsElapsed>,
+) {
+    let target = camera.get_single().expect("").target;
+    for (interaction, edit) in interaction_query.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        let (node, mut propulsion) = match crafts.get_mut(target) {
+            Ok(c) => c,
+            Err(_) => return,
+        };
+
+        match (edit, node) {
+            (ManeuverEdit::Add, None) => {
+                info!("Adding maneuver node");
+                commands
+                    .entity(target)
+                    .insert(ManeuverNode::new(elapsed.seconds + NEW_NODE_DELAY));
+            }
+            (ManeuverEdit::Delete, Some(node)) => {
+                info!("Removing maneuver node");
+                // Stop a running burn
+                if node.burn.is_some() {
+                    propulsion.throttle = 0.0;
+                    propulsion.fixed_direction = None;
+                }
+                commands.entity(target).remove::<ManeuverNode>();
+            }
+            (ManeuverEdit::Time(change), Some(mut node)) if node.burn.is_none() => {
+                node.time = (node.time + change).max(elapsed.seconds);
+            }
+            (ManeuverEdit::DeltaV(change), Some(mut node)) if node.burn.is_none() => {
+                node.delta_v += *change;
+            }
+            _ => (),
+        }
+    }
+}
+
+/// Shows the maneuver node of the focused craft
+fn update_display(
+    mut display: Query<&mut Text, With<ManeuverDisplay>>,
+    camera: Query<&FocusTarget>,
+    crafts: Query<(&Propulsion, Option<&ManeuverNode>)>,
+    elapsed: Res<PhysicsElapsed>,
+) {
+    let mut text = display.get_single_mut().expect("");
+    let target = camera.get_single().expect("").target;
+
+    text.sections[0].value = match crafts.get(target) {
+        Ok((propulsion, Some(node))) => {
+            let delta_v = node.delta_v.length();
+            let state = match node.burn {
+                Some(burn) => format!(
+                    "Burning, {:.1} m/s left",
+                    burn.length() - (propulsion.delivered_delta_v - node.burn_start)
+                ),
+                None => format!("Node in {:.0} s", node.time - elapsed.seconds),
+            };
+            format!(
+                "{}\nPrograde {:.1} m/s\nNormal   {:.1} m/s\nRadial   {:.1} m/s\nTotal {:.1} m/s, burn {:.1} s",
+                state,
+                node.delta_v.x,
+                node.delta_v.y,
+                node.delta_v.z,
+                delta_v,
+                propulsion.burn_time(delta_v)
+            )
+        }
+        Ok((_, None)) => "No maneuver node".into(),
+        Err(_) => "No craft selected".into(),
+    };
+}
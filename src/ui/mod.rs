@@ -13,8 +13,13 @@ use crate::objects::systemsets::CameraSets;
 use self::{
     button::set_button_ui_click,
     clock::UiClockPlugin,
+    integrator::UiIntegratorPlugin,
+    maneuver::UiManeuverPlugin,
+    orbitinfo::UiOrbitInfoPlugin,
+    propulsion::UiPropulsionPlugin,
     referenceframe::UiReferenceFramePlugin,
     resources::UiClicked,
+    rewind::UiRewindPlugin,
     systemsets::UiSets,
     window::{move_window, set_window_ui_click, toggle_hide_window},
 };
@@ -25,7 +30,12 @@ pub mod systemsets;
 mod button;
 mod clock;
 mod container;
+mod integrator;
+mod maneuver;
+mod orbitinfo;
+mod propulsion;
 mod referenceframe;
+mod rewind;
 mod simspeed;
 mod window;
 
@@ -53,8 +63,13 @@ impl PluginGroup for UiPlugins {
         PluginGroupBuilder::start::<Self>()
             .add(UiPlugin)
             .add(UiSimSpeedPlugin)
+            .add(UiRewindPlugin)
             .add(UiClockPlugin)
             .add(UiReferenceFramePlugin)
+            .add(UiIntegratorPlugin)
+            .add(UiOrbitInfoPlugin)
+            .add(UiPropulsionPlugin)
+            .add(UiManeuverPlugin)
     }
 }
 
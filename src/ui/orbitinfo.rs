@@ -0,0 +1,140 @@
+use bevy::{
+    app::{App, Plugin, Startup, Update},
+    asset::AssetServer,
+    core::Name,
+    ecs::{
+        component::Component,
+        entity::Entity,
+        query::With,
+        system::{Commands, Query, Res},
+    },
+    text::Text,
+    text::TextStyle,
+    ui::{node_bundles::TextBundle, FlexDirection},
+};
+
+use crate::{
+    objects::components::FocusTarget,
+    orbits::components::OrbitalElements,
+    physics::components::{Eclipse, EclipseState, PrimaryBody},
+};
+
+use super::{container::UiContainerBuilder, window::UiWindowBuilder};
+
+#[derive(Component)]
+struct OrbitInfoDisplay;
+
+pub struct UiOrbitInfoPlugin;
+
+impl Plugin for UiOrbitInfoPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, build_ui)
+            .add_systems(Update, update_orbit_info);
+    }
+}
+
+pub fn build_ui(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let info = commands
+        .spawn((
+            TextBundle::from_section(
+                "",
+                TextStyle {
+                    font: asset_server.load("fonts/Consolas.ttf"),
+                    font_size: 15.0,
+                    ..Default::default()
+                },
+            ),
+            OrbitInfoDisplay,
+        ))
+        .id();
+
+    let container = UiContainerBuilder::build(&mut commands, FlexDirection::Column, &[info]);
+
+    UiWindowBuilder::build(
+        &mut commands,
+        &asset_server,
+        "Orbit".into(),
+        container,
+        (10.0, 500.0),
+    );
+}
+
+/// Shows the orbital elements of the body the camera is focused on
+fn update_orbit_info(
+    mut display: Query<&mut Text, With<OrbitInfoDisplay>>,
+    camera: Query<&FocusTarget>,
+    bodies: Query<(&Name, &PrimaryBody, &OrbitalElements, Option<&Eclipse>)>,
+    names: Query<&Name>,
+) {
+    let mut text = display.get_single_mut().expect("");
+    let target = camera.get_single().expect("").target;
+
+    let (name, primary, elements, eclipse) = match bodies.get(target) {
+        Ok(b) => b,
+        Err(_) => {
+            text.sections[0].value = "No body selected".into();
+            return;
+        }
+    };
+
+    let primary_name = match names.get(primary.0) {
+        Ok(n) => n,
+        Err(_) => {
+            text.sections[0].value = format!("{} has no primary", name);
+            return;
+        }
+    };
+
+    let period = if elements.period.is_finite() {
+        format!("{:.3} h", elements.period / 3600.0)
+    } else {
+        "-".into()
+    };
+
+    text.sections[0].value = format!(
+        "{} around {}\n\
+        a    {:.3} km\n\
+        e    {:.6}\n\
+        i    {:.3} deg\n\
+        RAAN {:.3} deg\n\
+        AoP  {:.3} deg\n\
+        TA   {:.3} deg\n\
+        MA   {:.3} deg\n\
+        T    {}{}",
+        name,
+        primary_name,
+        elements.semi_major_axis / 1000.0,
+        elements.eccentricity,
+        elements.inclination.to_degrees(),
+        elements.ascending_node.to_degrees(),
+        elements.argument_of_periapsis.to_degrees(),
+        elements.true_anomaly.to_degrees(),
+        elements.mean_anomaly.to_degrees(),
+        period,
+        eclipse_info(eclipse, &names)
+    );
+}
+
+/// Shadow state of crafts, empty for bodies without an Eclipse
+fn eclipse_info(eclipse: Option<&Eclipse>, names: &Query<&Name>) -> String {
+    let Some(eclipse) = eclipse else {
+        return "".into();
+    };
+    if eclipse.star == Entity::PLACEHOLDER {
+        return "\nNo star".into();
+    }
+
+    let occulter = names
+        .get(eclipse.occulter)
+        .map_or("".into(), |n| format!(" by {}", n));
+    match eclipse.state {
+        EclipseState::Sunlit => "\nSunlit".into(),
+        EclipseState::Umbra => format!("\nUmbra{}", occulter),
+        EclipseState::Penumbra | EclipseState::Antumbra => format!(
+            "\n{:?}{}, {:.0}% lit",
+            eclipse.state,
+            occulter,
+            eclipse.illumination * 100.0
+        ),
+    }
+}
@@ -0,0 +1,193 @@
+use bevy::{
+    app::{App, Plugin, Startup, Update},
+    asset::AssetServer,
+    ecs::{
+        component::Component,
+        entity::Entity,
+        query::{Changed, With, Without},
+        schedule::IntoSystemConfigs,
+        system::{Commands, Query, Res},
+    },
+    input::{keyboard::KeyCode, Input},
+    log::info,
+    text::Text,
+    text::TextStyle,
+    time::Time,
+    ui::{node_bundles::TextBundle, FlexDirection, Interaction},
+};
+
+use crate::{
+    objects::components::FocusTarget,
+    physics::{
+        components::{Propulsion, ThrustDirection},
+        systemsets::PhysicsSet,
+    },
+};
+
+use super::{
+    button::{UiButtonBuilder, UiButtonStyle},
+    container::UiContainerBuilder,
+    systemsets::UiSets,
+    window::UiWindowBuilder,
+};
+
+/// Throttle change per second while the throttle keys are held
+const THROTTLE_RATE: f64 = 0.5;
+
+#[derive(Component)]
+struct DirectionChange(ThrustDirection);
+
+#[derive(Component)]
+struct PropulsionDisplay;
+
+pub struct UiPropulsionPlugin;
+
+impl Plugin for UiPropulsionPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, build_ui).add_systems(
+            Update,
+            (
+                (
+                    change_direction.in_set(UiSets::UiUpdateAll),
+                    control_throttle,
+                )
+                    .before(PhysicsSet::All),
+                update_display,
+            ),
+        );
+    }
+}
+
+pub fn build_ui(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let buttons: Vec<Entity> = ThrustDirection::ALL
+        .iter()
+        .map(|d| {
+            UiButtonBuilder::build(
+                &mut commands,
+                &asset_server,
+                DirectionChange(*d),
+                format!("{:?}", d),
+                UiButtonStyle::default(),
+            )
+        })
+        .collect();
+
+    let button_container =
+        UiContainerBuilder::build(&mut commands, FlexDirection::Row, buttons.as_slice());
+
+    let display = commands
+        .spawn((
+            TextBundle::from_section(
+                "",
+                TextStyle {
+                    font: asset_server.load("fonts/Consolas.ttf"),
+                    font_size: 15.0,
+                    ..Default::default()
+                },
+            ),
+            PropulsionDisplay,
+        ))
+        .id();
+
+    let container = UiContainerBuilder::build(
+        &mut commands,
+        FlexDirection::Column,
+        &[display, button_container],
+    );
+
+    UiWindowBuilder::build(
+        &mut commands,
+        &asset_server,
+        "Propulsion".into(),
+        container,
+        (400.0, 10.0),
+    );
+}
+
+///
+/// Controls the engine of the focused craft.
+/// Shift and Ctrl increase and decrease the throttle, Z sets full throttle and X cuts the engine.
+/// Keys 1 to 6 select the thrust direction.
+///
+fn control_throttle(
+    keys: Res<Input<KeyCode>>,
+    time: Res<Time>,
+    camera: Query<&FocusTarget>,
+    mut crafts: Query<&mut Propulsion>,
+) {
+    let target = camera.get_single().expect("").target;
+    let mut propulsion = match crafts.get_mut(target) {
+        Ok(p) => p,
+        Err(_) => return,
+    };
+
+    let change = THROTTLE_RATE * time.delta_seconds_f64();
+    if keys.pressed(KeyCode::ShiftLeft) {
+        propulsion.throttle = (propulsion.throttle + change).min(1.0);
+    }
+    if keys.pressed(KeyCode::ControlLeft) {
+        propulsion.throttle = (propulsion.throttle - change).max(0.0);
+    }
+    if keys.just_pressed(KeyCode::Z) {
+        propulsion.throttle = 1.0;
+    }
+    if keys.just_pressed(KeyCode::X) {
+        propulsion.throttle = 0.0;
+    }
+
+    let direction_keys = [
+        KeyCode::Key1,
+        KeyCode::Key2,
+        KeyCode::Key3,
+        KeyCode::Key4,
+        KeyCode::Key5,
+        KeyCode::Key6,
+    ];
+    for (key, direction) in direction_keys.iter().zip(ThrustDirection::ALL) {
+        if keys.just_pressed(*key) {
+            propulsion.direction = direction;
+        }
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn change_direction(
+    interaction_query: Query<
+        (&Interaction, &DirectionChange),
+        (Changed<Interaction>, With<DirectionChange>),
+    >,
+    camera: Query<&FocusTarget>,
+    mut crafts: Query<&mut Propulsion>,
+) {
+    let target = camera.get_single().expect("").target;
+    for (interaction, direction_change) in interaction_query.iter() {
+        if *interaction == Interaction::Pressed {
+            if let Ok(mut propulsion) = crafts.get_mut(target) {
+                info!("Setting thrust direction to {:?}", direction_change.0);
+                propulsion.direction = direction_change.0;
+            }
+        }
+    }
+}
+
+/// Shows the engine state of the focused craft
+fn update_display(
+    mut display: Query<&mut Text, (With<PropulsionDisplay>, Without<DirectionChange>)>,
+    camera: Query<&FocusTarget>,
+    crafts: Query<&Propulsion>,
+) {
+    let mut text = display.get_single_mut().expect("");
+    let target = camera.get_single().expect("").target;
+
+    text.sections[0].value = match crafts.get(target) {
+        Ok(propulsion) if propulsion.thrust > 0.0 => format!(
+            "Throttle {:.0}% {:?}\nFuel {:.1} kg\nDelta-v {:.1} m/s",
+            propulsion.throttle * 100.0,
+            propulsion.direction,
+            propulsion.fuel_mass,
+            propulsion.remaining_delta_v()
+        ),
+        Ok(_) => "No engine".into(),
+        Err(_) => "No craft selected".into(),
+    };
+}
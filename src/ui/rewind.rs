@@ -0,0 +1,241 @@
+use bevy::{
+    app::{App, Plugin, Startup, Update},
+    asset::AssetServer,
+    ecs::{
+        component::Component,
+        entity::Entity,
+        event::EventWriter,
+        query::{Changed, With},
+        system::{Commands, Local, Query, Res, ResMut},
+    },
+    hierarchy::BuildChildren,
+    log::info,
+    render::color::Color,
+    text::{Text, TextStyle},
+    ui::{
+        node_bundles::{ButtonBundle, NodeBundle, TextBundle},
+        FlexDirection, Interaction, RelativeCursorPosition, Style, Val,
+    },
+};
+
+use crate::{
+    physics::resources::{PhysicsElapsed, PhysicsTimeScale},
+    save::keyframes::{Keyframes, SeekEvent, SeekTarget},
+};
+
+use super::{
+    button::{UiButton, UiButtonBuilder, UiButtonStyle},
+    container::UiContainerBuilder,
+    window::UiWindowBuilder,
+};
+
+const SEC_PER_HOUR: f64 = 3600.0;
+const SEC_PER_DAY: f64 = 86400.0;
+
+#[derive(Component, Clone, Copy)]
+enum RewindAction {
+    /// Flips the sign of the time scale
+    Reverse,
+    /// Seeks back by the given simulated seconds
+    Back(f64),
+    /// Seeks to the earliest keyframe
+    Start,
+}
+
+#[derive(Component)]
+struct RecordingDisplay;
+
+/// Bar spanning the recorded time, clicking or dragging on it seeks to the time below the cursor
+#[derive(Component)]
+struct Scrubber;
+
+/// Part of the Scrubber up to the current time
+#[derive(Component)]
+struct ScrubberFill;
+
+pub struct UiRewindPlugin;
+
+impl Plugin for UiRewindPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, build_ui)
+            .add_systems(Update, (rewind, scrub, update_recording));
+    }
+}
+
+pub fn build_ui(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let buttons: Vec<Entity> = [
+        (RewindAction::Reverse, "Reverse"),
+        (RewindAction::Back(SEC_PER_HOUR), "-1h"),
+        (RewindAction::Back(SEC_PER_DAY), "-1d"),
+        (RewindAction::Start, "Start"),
+    ]
+    .into_iter()
+    .map(|(action, label)| {
+        UiButtonBuilder::build(
+            &mut commands,
+            &asset_server,
+            action,
+            label.into(),
+            UiButtonStyle::default(),
+        )
+    })
+    .collect();
+
+    let button_container =
+        UiContainerBuilder::build(&mut commands, FlexDirection::Row, buttons.as_slice());
+
+    let fill = commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(0.0),
+                    height: Val::Percent(100.0),
+                    ..Default::default()
+                },
+                background_color: Color::rgb(0.8, 0.8, 0.8).into(),
+                ..Default::default()
+            },
+            ScrubberFill,
+        ))
+        .id();
+    let scrubber = commands
+        .spawn((
+            ButtonBundle {
+                style: Style {
+                    width: Val::Px(200.0),
+                    height: Val::Px(12.0),
+                    ..Default::default()
+                },
+                background_color: Color::rgb(0.3, 0.3, 0.3).into(),
+                ..Default::default()
+            },
+            RelativeCursorPosition::default(),
+            Scrubber,
+            UiButton,
+        ))
+        .id();
+    commands.entity(scrubber).push_children(&[fill]);
+
+    let recording = commands
+        .spawn((
+            TextBundle::from_section(
+                "",
+                TextStyle {
+                    font: asset_server.load("fonts/Consolas.ttf"),
+                    font_size: 15.0,
+                    ..Default::default()
+                },
+            ),
+            RecordingDisplay,
+        ))
+        .id();
+
+    let container = UiContainerBuilder::build(
+        &mut commands,
+        FlexDirection::Column,
+        &[button_container, scrubber, recording],
+    );
+
+    UiWindowBuilder::build(
+        &mut commands,
+        &asset_server,
+        "Rewind".into(),
+        container,
+        (10.0, 200.0),
+    );
+}
+
+fn rewind(
+    interaction_query: Query<(&Interaction, &RewindAction), Changed<Interaction>>,
+    mut time_scale: ResMut<PhysicsTimeScale>,
+    mut seek: EventWriter<SeekEvent>,
+    elapsed: Res<PhysicsElapsed>,
+    keyframes: Res<Keyframes>,
+) {
+    for (interaction, action) in interaction_query.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        match *action {
+            RewindAction::Reverse => {
+                time_scale.0 = if time_scale.0 == 0 { -1 } else { -time_scale.0 };
+                info!("Reversing timescale to {}", time_scale.0);
+            }
+            RewindAction::Back(seconds) => {
+                let earliest = keyframes.earliest().unwrap_or(0.0);
+                seek.send(SeekEvent((elapsed.seconds - seconds).max(earliest)));
+            }
+            RewindAction::Start => {
+                if let Some(earliest) = keyframes.earliest() {
+                    seek.send(SeekEvent(earliest));
+                }
+            }
+        }
+    }
+}
+
+/// Simulated seconds which can be sought, from the earliest keyframe to the latest keyframe or the current time
+fn recorded_range(keyframes: &Keyframes, elapsed: &PhysicsElapsed) -> Option<(f64, f64)> {
+    let earliest = keyframes.earliest()?;
+    let latest = keyframes.latest()?.max(elapsed.seconds);
+    (latest > earliest).then_some((earliest, latest))
+}
+
+///
+/// Seeks to the time below the cursor while the Scrubber is pressed, whenever the cursor moves.
+/// The range is kept from the start of the drag, as seeking changes the current time.
+///
+fn scrub(
+    scrubber: Query<(&Interaction, &RelativeCursorPosition), With<Scrubber>>,
+    mut seek: EventWriter<SeekEvent>,
+    mut drag: Local<Option<((f64, f64), f32)>>,
+    elapsed: Res<PhysicsElapsed>,
+    keyframes: Res<Keyframes>,
+) {
+    let Ok((interaction, cursor)) = scrubber.get_single() else {
+        return;
+    };
+    let (Interaction::Pressed, Some(cursor)) = (interaction, cursor.normalized) else {
+        *drag = None;
+        return;
+    };
+    let Some((earliest, latest)) = drag
+        .map(|(range, _)| range)
+        .or_else(|| recorded_range(&keyframes, &elapsed))
+    else {
+        return;
+    };
+
+    let position = cursor.x.clamp(0.0, 1.0);
+    let current = Some(((earliest, latest), position));
+    if *drag != current {
+        seek.send(SeekEvent(earliest + (latest - earliest) * position as f64));
+        *drag = current;
+    }
+}
+
+fn update_recording(
+    mut display: Query<&mut Text, With<RecordingDisplay>>,
+    mut fill: Query<&mut Style, With<ScrubberFill>>,
+    keyframes: Res<Keyframes>,
+    elapsed: Res<PhysicsElapsed>,
+    seeking: Option<Res<SeekTarget>>,
+) {
+    let current = seeking.as_ref().map_or(elapsed.seconds, |target| target.0);
+    fill.get_single_mut().expect("").width = match recorded_range(&keyframes, &elapsed) {
+        Some((earliest, latest)) => Val::Percent(
+            (100.0 * (current - earliest) / (latest - earliest)).clamp(0.0, 100.0) as f32,
+        ),
+        None => Val::Percent(100.0),
+    };
+
+    display.get_single_mut().expect("").sections[0].value = match (seeking, keyframes.earliest()) {
+        (Some(target), _) => format!("Seeking to T+{:.2} d", target.0 / SEC_PER_DAY),
+        (None, Some(earliest)) => format!(
+            "{} keyframes since T+{:.2} d",
+            keyframes.0.len(),
+            earliest / SEC_PER_DAY
+        ),
+        (None, None) => "No keyframes".into(),
+    };
+}
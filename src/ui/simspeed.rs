@@ -2,8 +2,9 @@ use bevy::{
     app::{App, Plugin, Startup, Update},
     asset::AssetServer,
     ecs::{
+        change_detection::DetectChanges,
         component::Component,
-        query::{Changed, With, Without},
+        query::{Changed, With},
         system::{Commands, Query, Res, ResMut},
     },
     log::info,
@@ -21,7 +22,7 @@ use super::{
 };
 
 #[derive(Component)]
-struct SimSpeedChange(u16);
+struct SimSpeedChange(i32);
 
 #[derive(Component)]
 struct SimSpeedDisplay;
@@ -31,7 +32,7 @@ pub struct UiSimSpeedPlugin;
 impl Plugin for UiSimSpeedPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(Startup, build_ui)
-            .add_systems(Update, change_speed);
+            .add_systems(Update, (change_speed, update_speed));
     }
 }
 
@@ -104,18 +105,30 @@ fn change_speed(
         (&Interaction, &SimSpeedChange),
         (Changed<Interaction>, With<SimSpeedChange>),
     >,
-    mut display: Query<&mut Text, (With<SimSpeedDisplay>, Without<SimSpeedChange>)>,
     mut speed_scale: ResMut<PhysicsTimeScale>,
 ) {
     for (interaction, speed_change) in interaction_query.iter() {
         match *interaction {
             Interaction::Pressed => {
+                // Speed up in the current direction, also while rewinding
                 info!("Increase timescale by {}", speed_change.0);
-                speed_scale.0 += speed_change.0;
-                display.get_single_mut().expect("").sections[0].value =
-                    speed_scale.0.to_string() + "x";
+                if speed_scale.0 < 0 {
+                    speed_scale.0 -= speed_change.0;
+                } else {
+                    speed_scale.0 += speed_change.0;
+                }
             }
             _ => {}
         }
     }
 }
+
+/// Shows the timescale, which is also reversed by the rewind window and paused at the start
+fn update_speed(
+    mut display: Query<&mut Text, With<SimSpeedDisplay>>,
+    speed_scale: Res<PhysicsTimeScale>,
+) {
+    if speed_scale.is_changed() {
+        display.get_single_mut().expect("").sections[0].value = speed_scale.0.to_string() + "x";
+    }
+}
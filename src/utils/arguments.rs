@@ -1,11 +1,108 @@
-use argparse::{ArgumentParser, StoreTrue};
+use std::process::exit;
+
+use argparse::{ArgumentParser, Collect, Store, StoreOption, StoreTrue};
+use bevy::app::App;
+
+use super::data::{find_data_file, DataDir};
+
+use crate::{
+    import::CelestialFrame,
+    objects::scenario::{load_scenario, Scenario},
+    physics::{
+        epoch::Epoch,
+        resources::{
+            BodyCollisionPolicy, GravityOpeningAngle, GravityParallelism, GravitySoftening,
+            GravitySolver, ImpactPolicy, Integrator, IntegratorTolerance, PhysicsElapsed,
+            PhysicsTimeScale, PhysicsTimestep, PrimaryRule, ShadowModel,
+        },
+    },
+    save::{keyframes::KeyframeInterval, InitialSnapshot},
+};
 
 pub struct ParsedArguments {
     pub create_data: bool,
+    pub scenario: Option<Scenario>,
+    pub load: String,
+    pub save: String,
+    pub keyframe_interval: f64,
+    pub seek: Option<f64>,
+    pub integrator: Integrator,
+    pub timestep: f64,
+    pub tolerance: f64,
+    pub primary_rule: PrimaryRule,
+    pub impact_policy: ImpactPolicy,
+    pub body_collision: BodyCollisionPolicy,
+    pub softening: f64,
+    pub gravity_solver: GravitySolver,
+    pub opening_angle: f64,
+    pub parallelism: GravityParallelism,
+    pub shadow_model: ShadowModel,
+    pub epoch: Epoch,
+    pub horizons: Vec<String>,
+    pub import_center: String,
+    pub import_frame: CelestialFrame,
+    /// Epoch given by --epoch or the scenario, imports use the first row of the tables without it
+    pub import_epoch: Option<Epoch>,
+    pub benchmark_bodies: usize,
+    pub headless: bool,
+    pub duration: f64,
+    pub output: String,
+}
+impl ParsedArguments {
+    /// Overwrites the default physics settings with the ones given on the command line
+    pub fn insert_resources(&self, app: &mut App) {
+        app.insert_resource(self.integrator)
+            .insert_resource(PhysicsTimestep(self.timestep))
+            .insert_resource(IntegratorTolerance(self.tolerance))
+            .insert_resource(self.primary_rule)
+            .insert_resource(self.impact_policy)
+            .insert_resource(self.body_collision)
+            .insert_resource(GravitySoftening(self.softening))
+            .insert_resource(self.gravity_solver)
+            .insert_resource(GravityOpeningAngle(self.opening_angle))
+            .insert_resource(self.parallelism)
+            .insert_resource(self.shadow_model)
+            .insert_resource(PhysicsElapsed::starting_at(self.epoch));
+
+        if let Some(scenario) = &self.scenario {
+            if let Some(time_scale) = scenario.time_scale {
+                app.insert_resource(PhysicsTimeScale(time_scale));
+            }
+            app.insert_resource(scenario.clone());
+        }
+        app.insert_resource(KeyframeInterval(self.keyframe_interval));
+        if !self.load.is_empty() {
+            app.insert_resource(InitialSnapshot(find_data_file(DataDir::Saves, &self.load)));
+        }
+    }
 }
 
 pub fn parse_arguments() -> ParsedArguments {
     let mut create = false;
+    let mut scenario = String::new();
+    let mut load = String::new();
+    let mut save = String::new();
+    let mut keyframe_interval = 3600.0;
+    let mut seek: Option<f64> = None;
+    let mut integrator: Option<Integrator> = None;
+    let mut timestep: Option<f64> = None;
+    let mut tolerance: f64 = 1e-10;
+    let mut primary_rule = PrimaryRule::default();
+    let mut impact_policy = ImpactPolicy::default();
+    let mut body_collision = BodyCollisionPolicy::default();
+    let mut softening = 1e6;
+    let mut gravity_solver = GravitySolver::default();
+    let mut opening_angle = 0.5;
+    let mut parallelism = GravityParallelism::default();
+    let mut shadow_model = ShadowModel::default();
+    let mut epoch: Option<Epoch> = None;
+    let mut horizons = Vec::new();
+    let mut import_center = String::new();
+    let mut import_frame = CelestialFrame::default();
+    let mut benchmark_bodies = 0;
+    let mut headless = false;
+    let mut duration = 86400.0;
+    let mut output = String::new();
     {
         // this block limits scope of borrows by ap.refer() method
         let mut ap = ArgumentParser::new();
@@ -15,10 +112,179 @@ pub fn parse_arguments() -> ParsedArguments {
             StoreTrue,
             "Construct example data directory",
         );
+        ap.refer(&mut scenario).add_option(
+            &["-s", "--scenario"],
+            Store,
+            "Scenario file, or name of a scenario in the data directory, instead of all planet and craft files",
+        );
+        ap.refer(&mut load).add_option(
+            &["--load"],
+            Store,
+            "Restore a snapshot, by file or by name in the data directory, after spawning the bodies",
+        );
+        ap.refer(&mut save).add_option(
+            &["--save"],
+            Store,
+            "Write a snapshot, to a file or by name in the data directory, at the end of headless mode",
+        );
+        ap.refer(&mut keyframe_interval).add_option(
+            &["--keyframe-interval"],
+            Store,
+            "Simulated seconds between the keyframes recorded for rewinding",
+        );
+        ap.refer(&mut seek).add_option(
+            &["--seek"],
+            StoreOption,
+            "Seek back to the given simulated seconds at the end of headless mode, before writing the states",
+        );
+        ap.refer(&mut integrator).add_option(
+            &["-i", "--integrator"],
+            StoreOption,
+            "Integration scheme: euler, verlet, rk4, yoshida or dp45 (adaptive)",
+        );
+        ap.refer(&mut timestep).add_option(
+            &["-t", "--timestep"],
+            StoreOption,
+            "Simulated seconds advanced by a single physics step",
+        );
+        ap.refer(&mut tolerance).add_option(
+            &["--tolerance"],
+            Store,
+            "Relative error tolerance of the adaptive integrator",
+        );
+        ap.refer(&mut primary_rule).add_option(
+            &["--primary-rule"],
+            Store,
+            "Rule choosing the body a craft orbits: laplace (sphere of influence) or gravity",
+        );
+        ap.refer(&mut impact_policy).add_option(
+            &["--impact"],
+            Store,
+            "Outcome of a craft hitting a planet: land or destroy",
+        );
+        ap.refer(&mut body_collision).add_option(
+            &["--body-collision"],
+            Store,
+            "Handling of colliding planets: merge, bounce or soften",
+        );
+        ap.refer(&mut softening).add_option(
+            &["--softening"],
+            Store,
+            "Plummer softening length in m used by the soften body collision policy",
+        );
+        ap.refer(&mut gravity_solver).add_option(
+            &["--gravity"],
+            Store,
+            "Gravity solver: direct (exact sum) or barnes-hut (octree approximation)",
+        );
+        ap.refer(&mut opening_angle).add_option(
+            &["--theta"],
+            Store,
+            "Opening angle of the Barnes-Hut solver, smaller is more accurate",
+        );
+        ap.refer(&mut parallelism).add_option(
+            &["--parallel"],
+            Store,
+            "Gravity threading: serial, deterministic (bit-identical to serial) or fast",
+        );
+        ap.refer(&mut shadow_model).add_option(
+            &["--shadow"],
+            Store,
+            "Shadow geometry for eclipses and radiation pressure: cylindrical or conical",
+        );
+        ap.refer(&mut epoch).add_option(
+            &["--epoch"],
+            StoreOption,
+            "Start epoch as ISO 8601 date or Julian date, followed by utc (default), tt or tdb",
+        );
+        ap.refer(&mut horizons).add_option(
+            &["--horizons"],
+            Collect,
+            "Import the state vectors of a JPL Horizons vector table into the data directory and exit, repeatable",
+        );
+        ap.refer(&mut import_center).add_option(
+            &["--import-center"],
+            Store,
+            "Make imported states relative to this imported body, e.g. Earth",
+        );
+        ap.refer(&mut import_frame).add_option(
+            &["--import-frame"],
+            Store,
+            "Reference plane of imported states: ecliptic or icrf",
+        );
+        ap.refer(&mut benchmark_bodies).add_option(
+            &["--benchmark-gravity"],
+            Store,
+            "Compare speed and accuracy of the gravity solvers for the given number of bodies",
+        );
+        ap.refer(&mut headless).add_option(
+            &["--headless"],
+            StoreTrue,
+            "Propagate the bodies without window or rendering, then write their final states",
+        );
+        ap.refer(&mut duration).add_option(
+            &["-d", "--duration"],
+            Store,
+            "Simulated seconds to propagate in headless mode",
+        );
+        ap.refer(&mut output).add_option(
+            &["-o", "--output"],
+            Store,
+            "File the final states are written to in headless mode, stdout if omitted",
+        );
         ap.parse_args_or_exit();
     }
 
+    // Settings of the scenario are used where the command line does not give any
+    let scenario = (!scenario.is_empty()).then(|| load_scenario(&scenario));
+    let (scenario_integrator, scenario_timestep, scenario_epoch) = match &scenario {
+        Some(s) => (
+            Scenario::setting(&s.integrator).expect("Invalid integrator in scenario"),
+            s.timestep,
+            Scenario::setting(&s.epoch).expect("Invalid epoch in scenario"),
+        ),
+        None => (None, None, None),
+    };
+
+    let timestep = timestep.or(scenario_timestep).unwrap_or(1.0 / 60.0);
+    if !(timestep > 0.0 && timestep.is_finite()) {
+        eprintln!(
+            "The timestep must be a positive number of seconds, got {}",
+            timestep
+        );
+        exit(2);
+    }
+    if !(tolerance > 0.0 && tolerance.is_finite()) {
+        eprintln!("The tolerance must be a positive number, got {}", tolerance);
+        exit(2);
+    }
+
     ParsedArguments {
         create_data: create,
+        scenario,
+        load,
+        save,
+        keyframe_interval,
+        seek,
+        integrator: integrator.or(scenario_integrator).unwrap_or_default(),
+        timestep,
+        tolerance,
+        primary_rule,
+        impact_policy,
+        body_collision,
+        softening,
+        gravity_solver,
+        opening_angle,
+        parallelism,
+        shadow_model,
+        epoch: epoch.or(scenario_epoch).unwrap_or(Epoch::J2000),
+        import_epoch: epoch.or(scenario_epoch),
+        horizons,
+        import_center,
+        import_frame,
+        benchmark_bodies,
+        headless,
+        duration,
+        output,
     }
 }
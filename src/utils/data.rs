@@ -10,6 +10,8 @@ pub enum DataDir {
     Base,
     Planets,
     Crafts,
+    Scenarios,
+    Saves,
 }
 
 impl ToString for DataDir {
@@ -18,6 +20,8 @@ impl ToString for DataDir {
             DataDir::Base => "".into(),
             DataDir::Planets => "planets".into(),
             DataDir::Crafts => "crafts".into(),
+            DataDir::Scenarios => "scenarios".into(),
+            DataDir::Saves => "saves".into(),
         }
     }
 }
@@ -29,6 +33,20 @@ pub fn get_data_dir(dir: DataDir) -> PathBuf {
         .join(dir.to_string())
 }
 
+/// File given by a path, or by its name inside the data directory when no such path exists.
+/// Names without extension are completed to json files.
+pub fn find_data_file(dir: DataDir, path: &str) -> PathBuf {
+    let given = PathBuf::from(path);
+    if given.exists() || given.components().count() > 1 {
+        return given;
+    }
+    let mut named = get_data_dir(dir).join(path);
+    if named.extension().is_none() {
+        named.set_extension("json");
+    }
+    named
+}
+
 pub fn create_data(src: PathBuf) {
     let dst = get_data_dir(DataDir::Base);
     fs::create_dir_all(&dst).expect("");